@@ -11,20 +11,83 @@ pub struct Expression {
     pub ty: TypeId,
 }
 
-type Scope = ();
+/// where a variable lives at runtime: `depth` scopes out from the innermost one, at index `slot`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Local {
+    pub depth: usize,
+    pub slot : usize,
+}
 
 type AnyExpr = Box<Expression>;
 
+#[derive(Debug, Clone)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum TypeKind {
+    Named(String),
+    /// `Name<T1, T2>`
+    Generic(String, Vec<Type>),
+    /// `[T]`
+    Array(Box<Type>),
+    /// `(T1, T2,)`, where the trailing comma is what makes `(T,)` a tuple
+    Tuple(Vec<Type>),
+    /// `T1 | T2`
+    Union(Vec<Type>),
+}
+
 #[derive(Debug)]
 pub struct EnumVariant {
-    name: String,
-    payload: EnumPayload
+    pub name: String,
+    pub payload: EnumPayload
 }
 
 #[derive(Debug)]
 pub enum EnumPayload {
+    Unit,
     Tuple(Vec<Type>),
-    Struct(Vec<(String, Type)>),
+    Struct(Vec<(String, Option<Type>)>),
+}
+
+#[derive(Debug)]
+pub struct Pattern {
+    pub pt: Pat,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum Pat {
+    Literal {
+        src: TokenType,
+        negative: bool,
+        /// the type the literal is compared as, set by `inference::infer`
+        ty: TypeId,
+    },
+    Wildcard,
+    Binding {
+        name: String,
+        slot: usize,
+    },
+    Variant {
+        path   : AnyExpr,
+        payload: PatPayload,
+    },
+}
+
+#[derive(Debug)]
+pub enum PatPayload {
+    Unit,
+    Tuple(Vec<Pattern>),
+    Struct(Vec<(String, Pattern)>),
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: AnyExpr,
 }
 
 #[derive(Debug)]
@@ -46,7 +109,8 @@ pub enum Expr {
         right: AnyExpr,
     },
     Block(Option<AnyExpr>),
-    Module(Option<AnyExpr>, String),
+    /// the `usize` is the slot the module is declared in
+    Module(Option<AnyExpr>, String, usize),
     Call {
         callee: AnyExpr,
         args: Vec<AnyExpr>, /* Vec of Box doesn't sound like a good idea */
@@ -55,10 +119,23 @@ pub enum Expr {
         object: AnyExpr,
         name: String,
     },
+    /// `value as to`, only between number types
+    Cast {
+        value: AnyExpr,
+        to: Type,
+    },
+    /// `Path { field: value, ... }`
+    Construct {
+        of    : AnyExpr,
+        fields: Vec<(String, AnyExpr)>,
+    },
     Break {
         with: Option<AnyExpr>,
     },
     Continue,
+    Return {
+        with: Option<AnyExpr>,
+    },
     Use {
         imports: Vec<String>,
     },
@@ -80,25 +157,50 @@ pub enum Expr {
         op   : BinaryOp,
     },
     Identifier {
-        id: String,
+        id   : String,
+        /// filled in by the resolver, `None` for names that have to be looked up at runtime
+        local: Option<Local>,
     },
+    /// `_` in expression position; the parser turns it into a lambda's argument
+    Placeholder,
+    /// the arguments of `Fn` and `Lambda` take the first slots of the function's scope
     Fn {
         name: String,
         body: AnyExpr,
         args: Vec<String>,
+        /// one for each of `args`
+        annotations: Vec<Option<Type>>,
+        ret: Option<Type>,
+        slot: usize,
+    },
+    Lambda {
+        body: AnyExpr,
+        args: Vec<String>,
     },
     Let {
-        name : String,
-        value: Option<AnyExpr>,
+        name      : String,
+        value     : Option<AnyExpr>,
+        annotation: Option<Type>,
+        slot      : usize,
     },
     While {
         condition: AnyExpr,
         body     : AnyExpr,
     },
+    Match {
+        value: AnyExpr,
+        arms : Vec<MatchArm>,
+    },
     EnumDecl {
         name    : String,
         variants: Vec<EnumVariant>,
-    }
+        slot    : usize,
+    },
+    StructDecl {
+        name  : String,
+        fields: Vec<(String, Option<Type>)>,
+        slot  : usize,
+    },
 }
 
 #[derive(Debug)]
@@ -125,12 +227,110 @@ pub enum UnaryOp {
 }
 
 pub fn is_scope_barrier(expr: &Expression) -> bool {
-    matches!(expr.et, Expr::Block(_) | Expr::Module(_, _) | Expr::Loop { .. } | Expr::Fn { .. })
+    matches!(expr.et, Expr::Block(_) | Expr::Module(..) | Expr::Loop { .. } | Expr::Fn { .. } | Expr::Lambda { .. })
 }
 
-pub fn get_scope(expr: &Expression) -> Scope {
-    match expr.et {
-        
+/// the direct subexpressions of `expr`, in evaluation order
+pub fn children_mut(expr: &mut Expression) -> Vec<&mut Expression> {
+    match &mut expr.et {
+        Expr::Literal { .. }
+      | Expr::Continue
+      | Expr::Use { .. }
+      | Expr::Identifier { .. }
+      | Expr::Placeholder
+      | Expr::EnumDecl { .. }
+      | Expr::StructDecl { .. } => vec![],
+        Expr::Unary { right, .. } => vec![right],
+        Expr::Binary { left, right, .. }
+      | Expr::Semicolon { left, right }
+      | Expr::Assign { left, right }
+      | Expr::AssignOp { left, right, .. } => vec![left, right],
+        Expr::Block(inside) | Expr::Module(inside, ..) => inside.iter_mut().map(|x| &mut **x).collect(),
+        Expr::Call { callee, args } => {
+            let mut children = args.iter_mut().map(|x| &mut **x).collect::<Vec<_>>();
+            children.push(callee);
+            children
+        },
+        Expr::Property { object, .. } => vec![object],
+        Expr::Cast { value, .. } => vec![value],
+        Expr::Construct { of, fields } => {
+            let mut children = fields.iter_mut().map(|(_, x)| &mut **x).collect::<Vec<_>>();
+            children.push(of);
+            children
+        },
+        Expr::Break { with } | Expr::Return { with } => with.iter_mut().map(|x| &mut **x).collect(),
+        Expr::Loop { inside } => vec![inside],
+        Expr::If { condition, then, else_ } => {
+            let mut children: Vec<&mut Expression> = vec![condition, then];
+            if let Some(else_) = else_ {
+                children.push(else_);
+            }
+            children
+        },
+        Expr::Fn { body, .. } | Expr::Lambda { body, .. } => vec![body],
+        Expr::Let { value, .. } => value.iter_mut().map(|x| &mut **x).collect(),
+        Expr::While { condition, body } => vec![condition, body],
+        Expr::Match { value, arms } => {
+            let mut children: Vec<&mut Expression> = vec![value];
+            children.extend(arms.iter_mut().map(|arm| &mut *arm.body));
+            children
+        },
+    }
+}
+
+pub fn stringify_type(r#type: &Type) -> String {
+    match &r#type.kind {
+        TypeKind::Named(name) => name.clone(),
+        TypeKind::Generic(name, arguments) => format!("{}<{}>", name, arguments.iter().map(stringify_type).collect::<Vec<_>>().join(", ")),
+        TypeKind::Array(item) => format!("[{}]", stringify_type(item)),
+        TypeKind::Tuple(items) => format!("({},)", items.iter().map(stringify_type).collect::<Vec<_>>().join(", ")),
+        TypeKind::Union(variants) => variants.iter().map(stringify_type).collect::<Vec<_>>().join(" | "),
+    }
+}
+
+/// `name: T, name, ...` like in a struct declaration
+fn stringify_fields(fields: &[(String, Option<Type>)]) -> String {
+    fields.iter().map(|(name, r#type)| match r#type {
+        Some(r#type) => format!("{}: {}", name, stringify_type(r#type)),
+        None => name.clone(),
+    }).collect::<Vec<_>>().join(", ")
+}
+
+pub fn stringify_pattern(pattern: &Pattern) -> String {
+    match &pattern.pt {
+        Pat::Literal { src, negative, .. } => {
+            let sign = if *negative { "-" } else { "" };
+            match src {
+                TokenType::BLiteral(v) => v.to_string(),
+                TokenType::FLiteral { value, .. } => format!("{sign}{value}"),
+                TokenType::SLiteral { value, .. } => format!("'{value}'"),
+                TokenType::ILiteral { value, kind, .. } => match kind {
+                    IntLiteralType::Hexadecimal => format!("{sign}0x{value}"),
+                    IntLiteralType::Decimal     => format!("{sign}{value}"),
+                    IntLiteralType::Octal       => format!("{sign}0o{value}"),
+                    IntLiteralType::Binary      => format!("{sign}0b{value}"),
+                },
+                _ => ice!("unreachable; only [IFSB]Literal should be reachable here")
+            }
+        },
+        Pat::Wildcard => "_".to_owned(),
+        Pat::Binding { name, .. } => format!("#{name}"),
+        Pat::Variant { path, payload } => {
+            let path = stringify_path(path);
+            match payload {
+                PatPayload::Unit => path,
+                PatPayload::Tuple(items) => format!("{}({})", path, items.iter().map(stringify_pattern).collect::<Vec<_>>().join(", ")),
+                PatPayload::Struct(fields) => format!("{} {{ {} }}", path, fields.iter().map(|(name, pattern)| format!("{}: {}", name, stringify_pattern(pattern))).collect::<Vec<_>>().join(", ")),
+            }
+        },
+    }
+}
+
+fn stringify_path(expr: &Expression) -> String {
+    match &expr.et {
+        Expr::Identifier { id, .. } => id.clone(),
+        Expr::Property { object, name } => format!("{}.{}", stringify_path(object), name),
+        _ => ice!("variant patterns only contain paths"),
     }
 }
 
@@ -180,22 +380,26 @@ fn show_tree_impl(expr: &Expression, depth: usize) {
                 }
             }
         }
-        Expr::EnumDecl { name, variants } => {
+        Expr::EnumDecl { name, variants, .. } => {
             eprint!("{}enum {} {{\x1B[0m", bracket_color!(), name);
-            if variants.len() == 0 {
+            if variants.is_empty() {
                 eprintln!("{}}}\x1B[0m", bracket_color!());
             } else {
                 eprintln!();
                 for variant in variants {
                     eprintln!("{}{} {}", " ".repeat(depth * 2 + 2), variant.name, match &variant.payload {
-                        EnumPayload::Tuple(types) => format!("({})", types.iter().map(|x| stringify_type(*x)).collect::<Vec<_>>().join(", ")),
-                        EnumPayload::Struct(fields) => format!("{{{}}}", fields.iter().map(|(name, r#type)| format!("{}: {}", name, stringify_type(*r#type))).collect::<Vec<_>>().join(", ")),
+                        EnumPayload::Unit => String::new(),
+                        EnumPayload::Tuple(types) => format!("({})", types.iter().map(stringify_type).collect::<Vec<_>>().join(", ")),
+                        EnumPayload::Struct(fields) => format!("{{{}}}", stringify_fields(fields)),
                     })
                 }
                 eprintln!("{}{}}}\x1B[0m", " ".repeat(depth * 2), bracket_color!());
             }
         }
-        Expr::Module(inside, name) => {
+        Expr::StructDecl { name, fields, .. } => {
+            eprintln!("{}struct {} {{\x1B[0m{}{}}}\x1B[0m", bracket_color!(), name, stringify_fields(fields), bracket_color!());
+        }
+        Expr::Module(inside, name, _) => {
             eprint!("{}mod {} {{\x1B[0m", bracket_color!(), name);
             match inside {
                 None => eprintln!("{}}}\x1B[0m", bracket_color!()),
@@ -262,6 +466,20 @@ fn show_tree_impl(expr: &Expression, depth: usize) {
             show_tree_impl(object, depth + 1);
             eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
         },
+        Expr::Cast { value, to } => {
+            eprintln!("{}(\x1B[32mas\x1B[0m {}", bracket_color!(), stringify_type(to));
+            show_tree_impl(value, depth + 1);
+            eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
+        },
+        Expr::Construct { of, fields } => {
+            eprintln!("{}(\x1B[32mconstruct\x1B[0m", bracket_color!());
+            show_tree_impl(of, depth + 1);
+            for (name, value) in fields {
+                eprintln!("{}\x1B[32mfield\x1B[0m {}", " ".repeat(depth * 2), name);
+                show_tree_impl(value, depth + 1);
+            }
+            eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
+        },
         Expr::Break { with } => {
             if let Some(thing) = with {
                 eprintln!("{}(\x1B[32mbreak\x1B[0m", bracket_color!());
@@ -271,6 +489,15 @@ fn show_tree_impl(expr: &Expression, depth: usize) {
                 eprintln!("{0}(\x1B[32mbreak{0})\x1B[0m", bracket_color!())
             }
         },
+        Expr::Return { with } => {
+            if let Some(thing) = with {
+                eprintln!("{}(\x1B[32mreturn\x1B[0m", bracket_color!());
+                show_tree_impl(thing, depth + 1);
+                eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
+            } else {
+                eprintln!("{0}(\x1B[32mreturn{0})\x1B[0m", bracket_color!())
+            }
+        },
         Expr::Loop { inside } => {
             eprintln!("{}(\x1B[32mloop\x1B[0m", bracket_color!());
             show_tree_impl(inside, depth + 1);
@@ -311,16 +538,33 @@ fn show_tree_impl(expr: &Expression, depth: usize) {
             show_tree_impl(right, depth + 1);
             eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
         },
-        Expr::Identifier { id } => {
-            eprintln!("\x1B[31m#{}\x1B[0m", id);
+        Expr::Identifier { id, local } => match local {
+            Some(Local { depth, slot }) => eprintln!("\x1B[31m#{}\x1B[37m@{}:{}\x1B[0m", id, depth, slot),
+            None => eprintln!("\x1B[31m#{}\x1B[0m", id),
+        },
+        Expr::Placeholder => {
+            eprintln!("\x1B[31m_\x1B[0m");
+        },
+        Expr::Fn { name, body, args, annotations, ret, .. } => {
+            let args = args.iter().zip(annotations).map(|(arg, annotation)| match annotation {
+                Some(r#type) => format!("{}: {}", arg, stringify_type(r#type)),
+                None => arg.clone(),
+            }).collect::<Vec<_>>();
+            let ret = ret.as_ref().map_or_else(String::new, |r#type| format!(" -> {}", stringify_type(r#type)));
+            eprintln!("{}(\x1B[32mfn \x1B[31m#{}\x1B[0m [{}]{}", bracket_color!(), name, args.join(", "), ret);
+            show_tree_impl(body, depth + 1);
+            eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
         },
-        Expr::Fn { name, body, args } => {
-            eprintln!("{}(\x1B[32mfn \x1B[31m#{}\x1B[0m [{}]", bracket_color!(), name, args.join(", "));
+        Expr::Lambda { body, args } => {
+            eprintln!("{}(\x1B[32mlambda\x1B[0m [{}]", bracket_color!(), args.join(", "));
             show_tree_impl(body, depth + 1);
             eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
         },
-        Expr::Let { name, value } => {
+        Expr::Let { name, value, annotation, .. } => {
             eprint!("{}(\x1B[32mlet \x1B[31m#{}\x1B[0m", bracket_color!(), name);
+            if let Some(r#type) = annotation {
+                eprint!(": {}", stringify_type(r#type));
+            }
             if let Some(x) = value {
                 eprintln!();
                 show_tree_impl(x, depth + 1);
@@ -335,5 +579,14 @@ fn show_tree_impl(expr: &Expression, depth: usize) {
             show_tree_impl(body, depth + 1);
             eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
         },
+        Expr::Match { value, arms } => {
+            eprintln!("{}(\x1B[32mmatch\x1B[0m", bracket_color!());
+            show_tree_impl(value, depth + 1);
+            for arm in arms {
+                eprintln!("{}\x1B[32mcase\x1B[0m {}", " ".repeat(depth * 2), stringify_pattern(&arm.pattern));
+                show_tree_impl(&arm.body, depth + 1);
+            }
+            eprintln!("{}{})\x1B[0m", " ".repeat(depth * 2), bracket_color!());
+        },
     }
 }
\ No newline at end of file
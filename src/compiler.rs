@@ -1,11 +1,20 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc};
 
-use crate::{ast::{Expression, Expr, UnaryOp, BinaryOp}, vm, ice::ice, tokens::IntLiteralType};
+use crate::{ast::{Expression, Expr, UnaryOp, BinaryOp, Pattern, Pat, PatPayload, EnumPayload, Local}, vm, ice::ice, tokens::{IntLiteralType, TokenType}, errors::{codes, make_error, Error, Severity}, loader::Span, typing::{Float, Integer, Machine, Type, TypeId, TypeInfo}};
 
 #[derive(Debug)]
 #[must_use]
 pub(crate) struct Target(usize);
 
+/// where `break` and `continue` jump to, and what they have to clean up on the way
+#[derive(Debug, Clone, Copy)]
+struct LoopContext {
+    start: usize,
+    end: usize,
+    temporaries: usize,
+    scope_depth: usize,
+}
+
 #[derive(Debug, Default)]
 pub struct ProgramBuilder {
     debug_instructions: bool,
@@ -14,12 +23,24 @@ pub struct ProgramBuilder {
     names: Vec<String>,
     target_no: usize,
     end_stuff: Vec<(Option<vm::Instruction>, ProgramBuilder)>,
-    #[cfg(feature = "instruction_sources")]
-    src_stack: Vec<String>,
+    errors: Vec<Error>,
+    loops: Vec<LoopContext>,
+    /// values currently sitting on the stack that belong to an expression that isn't fully evaluated yet
+    temporaries: usize,
+    /// scopes opened since the start of the current function (or of the program)
+    scope_depth: usize,
+    /// only set while building a function body, `return` isn't allowed anywhere else
+    in_function: bool,
+    /// the span of each instruction, `None` for the ones that don't come from any expression
+    spans: Vec<Option<Span>>,
+    /// the spans of the expressions being lowered, innermost last
+    span_stack: Vec<Span>,
+    /// what `inference::infer` found, shared with the builders of the functions inside
+    types: Rc<Machine>,
 }
 
 impl ProgramBuilder {
-    pub fn new(debug_instructions: bool) -> ProgramBuilder {
+    pub fn new(debug_instructions: bool, types: Machine) -> ProgramBuilder {
         Self {
             debug_instructions,
             instructions: vec![],
@@ -27,11 +48,21 @@ impl ProgramBuilder {
             names: vec![],
             target_no: 0,
             end_stuff: vec![],
-            #[cfg(feature = "instruction_sources")]
-            src_stack: vec![],
+            errors: vec![],
+            loops: vec![],
+            temporaries: 0,
+            scope_depth: 0,
+            in_function: false,
+            spans: vec![],
+            span_stack: vec![],
+            types: Rc::new(types),
         }
     }
 
+    fn for_function(&self) -> ProgramBuilder {
+        ProgramBuilder { debug_instructions: self.debug_instructions, types: self.types.clone(), ..ProgramBuilder::default() }
+    }
+
     pub fn add_constant(&mut self, constant: vm::Value) -> usize {
         for (index, value) in self.constants.iter().enumerate() {
             if *value == constant {
@@ -53,17 +84,12 @@ impl ProgramBuilder {
     }
 
     pub fn emit(&mut self, instruction: vm::Instruction) {
-        #[cfg(feature = "instruction_sources")]
-        {
-            let name = self.src_stack.join(">");
-            let index = self.add_name(&name);
-            self.instructions.push(vm::Instruction::CodegenHelper(index));
-        }
-        self.instructions.push(instruction)
+        self.instructions.push(instruction);
+        self.spans.push(self.span_stack.last().copied())
     }
 
     pub fn target(&mut self) -> usize {
-        self.instructions.push(vm::Instruction::JumpTarget(self.target_no));
+        self.emit(vm::Instruction::JumpTarget(self.target_no));
         self.target_no += 1;
         self.target_no - 1
     }
@@ -74,7 +100,7 @@ impl ProgramBuilder {
     }
 
     pub(crate) fn add_reserved(&mut self, t: Target) {
-        self.instructions.push(vm::Instruction::JumpTarget(t.0))
+        self.emit(vm::Instruction::JumpTarget(t.0))
     }
 
     pub fn stack_padding(&mut self) {
@@ -82,7 +108,8 @@ impl ProgramBuilder {
     }
 
     pub fn flattened(mut self) -> Self {
-        for (maybe_instruction, element) in self.end_stuff.into_iter().map(|x| (x.0, x.1.flattened())) {
+        for (maybe_instruction, mut element) in self.end_stuff.into_iter().map(|x| (x.0, x.1.flattened())) {
+            self.errors.append(&mut element.errors);
             macro_rules! self_add_constant {
                 ($cst:expr) => {'self_add_block: {
                     let constant = $cst;
@@ -108,8 +135,10 @@ impl ProgramBuilder {
                 }}
             }
             self.instructions.push(vm::Instruction::Terminate);
+            self.spans.push(None);
             if let Some(i) = maybe_instruction {
                 self.instructions.push(i);
+                self.spans.push(None);
             }
             let mut jump_map = HashMap::<usize, usize>::new();
             let mut constant_map = HashMap::<usize, usize>::new();
@@ -148,20 +177,17 @@ impl ProgramBuilder {
                     vm::Instruction::FunctionTag(n) => {
                         vm::Instruction::FunctionTag(*name_map.get(&n).unwrap())
                     },
-                    vm::Instruction::EndAndNameScope(n) => {
-                        vm::Instruction::EndAndNameScope(*name_map.get(&n).unwrap())
-                    },
                     vm::Instruction::AccessProperty(n) => {
                         vm::Instruction::AccessProperty(*name_map.get(&n).unwrap())
                     },
-                    vm::Instruction::CodegenHelper(n) => {
-                        vm::Instruction::CodegenHelper(*name_map.get(&n).unwrap())
+                    vm::Instruction::StoreProperty(n) => {
+                        vm::Instruction::StoreProperty(*name_map.get(&n).unwrap())
                     },
                     vm::Instruction::Store(n) => {
                         vm::Instruction::Store(*name_map.get(&n).unwrap())
                     },
-                    vm::Instruction::AssignStore(n) => {
-                        vm::Instruction::AssignStore(*name_map.get(&n).unwrap())
+                    vm::Instruction::DeclareLocal(slot, n) => {
+                        vm::Instruction::DeclareLocal(slot, *name_map.get(&n).unwrap())
                     },
                     vm::Instruction::JumpTarget(n) => {
                         vm::Instruction::JumpTarget(*jump_map.get(&n).unwrap())
@@ -172,36 +198,38 @@ impl ProgramBuilder {
                     vm::Instruction::ConditionalJumpTo(target) => {
                         vm::Instruction::ConditionalJumpTo(*jump_map.get(&target).unwrap())
                     },
-                    vm::Instruction::JumpRefTo(target) => {
-                        vm::Instruction::JumpRefTo(*jump_map.get(&target).unwrap())
-                    },
                     anything => anything
                 };
                 self.instructions.push(out);
             }
+            self.spans.append(&mut element.spans);
         };
         self.end_stuff = vec![];
         self
     }
 
-    pub fn finish(self) -> vm::Program {
-        self.flattened().finish_()
+    pub fn finish(self) -> Result<vm::Program, Vec<Error>> {
+        let flattened = self.flattened();
+        if flattened.errors.is_empty() {
+            Ok(flattened.finish_())
+        } else {
+            Err(flattened.errors)
+        }
     }
 
     fn finish_(mut self) -> vm::Program {
         let mut new_instructions = Vec::<vm::Instruction>::with_capacity(self.instructions.len());
+        let mut spans = Vec::<Option<Span>>::with_capacity(self.spans.len());
         self.instructions.reverse();
-        while let Some(instruction) = self.instructions.pop() {
-            #[cfg(not(feature = "instruction_sources"))]
-            if matches!(instruction, vm::Instruction::PushNothing) {
-                if !self.instructions.is_empty() {
-                    if matches!(self.instructions[self.instructions.len() - 1], vm::Instruction::Discard) {
-                        self.instructions.pop();
-                        continue
-                    }
-                }
+        self.spans.reverse();
+        while let (Some(instruction), Some(span)) = (self.instructions.pop(), self.spans.pop()) {
+            if matches!(instruction, vm::Instruction::PushNothing) && matches!(self.instructions.last(), Some(vm::Instruction::Discard)) {
+                self.instructions.pop();
+                self.spans.pop();
+                continue
             }
             new_instructions.push(instruction);
+            spans.push(span);
         }
         vm::Program {
             instructions: new_instructions.iter().map(|instruction| {
@@ -216,10 +244,6 @@ impl ProgramBuilder {
                         let position = new_instructions.iter().position(|x| matches!(x, vm::Instruction::JumpTarget(k) if k == target)).unwrap();
                         vm::Instruction::ConditionalJump(position)
                     },
-                    vm::Instruction::JumpRefTo(target) => {
-                        let position = new_instructions.iter().position(|x| matches!(x, vm::Instruction::JumpTarget(k) if k == target)).unwrap();
-                        vm::Instruction::PushJumpRef(position)
-                    }
                     any => any.clone(),
                 }
             }).collect::<Vec<_>>(),
@@ -230,65 +254,55 @@ impl ProgramBuilder {
                 }
             }).collect::<Vec<_>>(),
             names: self.names,
+            spans,
         }
     }
 
-    #[cfg(feature = "instruction_sources")]
-    fn start_src(&mut self, tag: &str) {
-        self.src_stack.push(tag.to_owned())
+    fn open_scope(&mut self) {
+        self.emit(vm::Instruction::NewScope);
+        self.scope_depth += 1;
     }
 
-    #[cfg(feature = "instruction_sources")]
-    fn end_src(&mut self) {
-        let _ = self.src_stack.pop();
+    fn close_scope(&mut self, instruction: vm::Instruction) {
+        self.emit(instruction);
+        self.scope_depth -= 1;
+    }
+
+    /// discards the temporaries and closes the scopes that were opened past the given depths, keeping the top of the stack if `keep_top` is set
+    fn unwind_to(&mut self, temporaries: usize, scope_depth: usize, keep_top: bool) {
+        for _ in temporaries..self.temporaries {
+            if keep_top {
+                self.emit(vm::Instruction::Swap);
+            }
+            self.emit(vm::Instruction::Discard);
+        }
+        for _ in scope_depth..self.scope_depth {
+            self.emit(vm::Instruction::EndScope);
+        }
     }
 }
 
 pub fn lower(expression: &Expression, builder: &mut ProgramBuilder) {
+    builder.span_stack.push(expression.span);
+    lower_impl(expression, builder);
+    builder.span_stack.pop();
+}
+
+fn lower_impl(expression: &Expression, builder: &mut ProgramBuilder) {
     match &expression.et {
-        Expr::Literal { src } => match src {
-            crate::tokens::TokenType::ILiteral { value, kind, .. } => {
-                #[cfg(feature = "instruction_sources")]
-                builder.start_src("literal");
-                let constant = builder.add_constant(vm::Value::Num(match kind {
-                    IntLiteralType::Hexadecimal => i64::from_str_radix(value, 16).unwrap() as f64,
-                    IntLiteralType::Decimal => value.parse::<f64>().unwrap(),
-                    IntLiteralType::Octal => i64::from_str_radix(value, 8).unwrap() as f64,
-                    IntLiteralType::Binary => i64::from_str_radix(value, 2).unwrap() as f64,
-                }));
-                builder.emit(vm::Instruction::LoadConst(constant));
-                #[cfg(feature = "instruction_sources")]
-                builder.end_src()
-            },
-            crate::tokens::TokenType::FLiteral { value, .. } => {
-                #[cfg(feature = "instruction_sources")]
-                builder.start_src("literal");
-                let constant = builder.add_constant(vm::Value::Num(value.parse::<f64>().unwrap() /* handle this in a better way...? */));
-                builder.emit(vm::Instruction::LoadConst(constant));
-                #[cfg(feature = "instruction_sources")]
-                builder.end_src()
-            },
-            crate::tokens::TokenType::SLiteral { value, .. } => {
-                #[cfg(feature = "instruction_sources")]
-                builder.start_src("literal");
-                let constant = builder.add_constant(vm::Value::Str(value.clone()));
-                builder.emit(vm::Instruction::LoadConst(constant));
-                #[cfg(feature = "instruction_sources")]
-                builder.end_src()
-            },
-            crate::tokens::TokenType::BLiteral(val) => {
-                #[cfg(feature = "instruction_sources")]
-                builder.start_src("literal");
-                let constant = builder.add_constant(vm::Value::Bool(*val));
-                builder.emit(vm::Instruction::LoadConst(constant));
-                #[cfg(feature = "instruction_sources")]
-                builder.end_src()
-            },
-            _ => ice!("literal is not a literal")
+        Expr::Literal { src } => {
+            let value = literal_value(src, expression.ty, false, expression.span, builder);
+            let constant = builder.add_constant(value);
+            builder.emit(vm::Instruction::LoadConst(constant));
+        },
+        // so that the most negative value of a type can be written
+        Expr::Unary { op: UnaryOp::Neg, right } if matches!(right.et, Expr::Literal { src: TokenType::ILiteral { .. } | TokenType::FLiteral { .. } }) => {
+            let Expr::Literal { src } = &right.et else { ice!("checked by the guard") };
+            let value = literal_value(src, right.ty, true, expression.span, builder);
+            let constant = builder.add_constant(value);
+            builder.emit(vm::Instruction::LoadConst(constant));
         },
         Expr::Unary { op, right } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("unary");
             lower(right, builder);
             match op {
                 UnaryOp::Neg   => builder.emit(vm::Instruction::Negate),
@@ -296,14 +310,17 @@ pub fn lower(expression: &Expression, builder: &mut ProgramBuilder) {
                 UnaryOp::Show  => builder.emit(vm::Instruction::Show),
                 UnaryOp::Panic => builder.emit(vm::Instruction::Panic),
             }
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src()
         },
         Expr::Binary { op, left, right } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("binary_op");
             lower(left, builder);
+            builder.temporaries += 1;
             lower(right, builder);
+            builder.temporaries -= 1;
+            match (is_untyped_literal(left, builder), is_untyped_literal(right, builder)) {
+                (true, false) => builder.emit(vm::Instruction::ConvertLiteral(1)),
+                (false, true) => builder.emit(vm::Instruction::ConvertLiteral(0)),
+                _ => (),
+            }
             builder.emit(match op {
                 BinaryOp::Add => vm::Instruction::Add,
                 BinaryOp::Sub => vm::Instruction::Subtract,
@@ -317,94 +334,158 @@ pub fn lower(expression: &Expression, builder: &mut ProgramBuilder) {
                 BinaryOp::Geq => vm::Instruction::GreaterEq,
                 BinaryOp::Neq => vm::Instruction::CheckInequality,
             });
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
         },
         Expr::Semicolon { left, right } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src(";");
             lower(left, builder);
             builder.emit(vm::Instruction::Discard);
             lower(right, builder);
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src()
         },
         Expr::Block(maybe_inside) => if let Some(inside) = maybe_inside {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("block");
-            builder.emit(vm::Instruction::NewScope);
+            builder.open_scope();
             lower(inside, builder);
-            builder.emit(vm::Instruction::EndScope);
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src()
+            builder.close_scope(vm::Instruction::EndScope);
         } else {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("block");
             builder.stack_padding();
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
         }, 
-        Expr::Module(maybe_inside, name) => if let Some(inside) = maybe_inside {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("module");
-            builder.emit(vm::Instruction::NewScope);
+        Expr::Module(maybe_inside, name, slot) => if let Some(inside) = maybe_inside {
+            builder.open_scope();
             lower(inside, builder);
+            builder.close_scope(vm::Instruction::EndModuleScope);
             let name_index = builder.add_name(name);
-            builder.emit(vm::Instruction::EndAndNameScope(name_index));
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
+            builder.emit(vm::Instruction::DeclareLocal(*slot, name_index));
         } else {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("module");
             builder.emit(vm::Instruction::NewScope);
             builder.stack_padding();
+            builder.emit(vm::Instruction::EndModuleScope);
             let name_index = builder.add_name(name);
-            builder.emit(vm::Instruction::EndAndNameScope(name_index));
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
+            builder.emit(vm::Instruction::DeclareLocal(*slot, name_index));
         }, 
         Expr::Call { callee, args } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("call");
-            let target = builder.reserve_target();
-            builder.emit(vm::Instruction::JumpRefTo(target.0));
             for arg in args {
                 lower(arg, builder);
+                builder.temporaries += 1;
             }
             lower(callee, builder);
-            builder.emit(vm::Instruction::Call);
-            builder.add_reserved(target);
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
+            builder.temporaries -= args.len();
+            builder.emit(vm::Instruction::Call(args.len()));
         },
         Expr::Property { object, name } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("property");
             lower(object, builder);
             let name_index = builder.add_name(name);
             builder.emit(vm::Instruction::AccessProperty(name_index));
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
         },
-        Expr::Break { .. } => todo!(),
-        Expr::Continue => todo!(),
+        Expr::Cast { value, .. } => {
+            lower(value, builder);
+            let to = builder.types.reconstruct(expression.ty).unwrap_or_else(|_| ice!("casts are always to a known type"));
+            match (to.integer(), to.float()) {
+                (Some(integer), _) => builder.emit(vm::Instruction::ConvertToInteger(integer)),
+                (_, Some(float)) => builder.emit(vm::Instruction::ConvertToFloat(float)),
+                _ => ice!("inference only allows casts to numbers, not {:?}", to),
+            }
+        },
+        Expr::Construct { of, fields } => {
+            for (name, value) in fields {
+                let constant = builder.add_constant(vm::Value::Str(name.clone()));
+                builder.emit(vm::Instruction::LoadConst(constant));
+                builder.temporaries += 1;
+                lower(value, builder);
+                builder.temporaries += 1;
+            }
+            lower(of, builder);
+            builder.temporaries -= fields.len() * 2;
+            builder.emit(vm::Instruction::Construct(fields.len()));
+        },
+        Expr::EnumDecl { name, variants, slot } => {
+            let def = vm::EnumDef {
+                name: name.clone(),
+                variants: variants.iter().map(|variant| vm::VariantDef {
+                    name: variant.name.clone(),
+                    fields: match &variant.payload {
+                        EnumPayload::Unit => vm::VariantFields::Unit,
+                        EnumPayload::Tuple(types) => vm::VariantFields::Tuple(types.len()),
+                        EnumPayload::Struct(fields) => vm::VariantFields::Struct(fields.iter().map(|(name, _)| name.clone()).collect()),
+                    },
+                }).collect(),
+            };
+            let constant = builder.add_constant(vm::Value::Enum(Rc::new(def)));
+            builder.emit(vm::Instruction::LoadConst(constant));
+            builder.emit(vm::Instruction::Duplicate);
+            let name_index = builder.add_name(name);
+            builder.emit(vm::Instruction::DeclareLocal(*slot, name_index));
+        },
+        Expr::StructDecl { name, fields, slot } => {
+            let def = vm::StructDef {
+                name: name.clone(),
+                fields: fields.iter().map(|(name, _)| name.clone()).collect(),
+            };
+            let constant = builder.add_constant(vm::Value::StructDef(Rc::new(def)));
+            builder.emit(vm::Instruction::LoadConst(constant));
+            builder.emit(vm::Instruction::Duplicate);
+            let name_index = builder.add_name(name);
+            builder.emit(vm::Instruction::DeclareLocal(*slot, name_index));
+        },
+        Expr::Break { with } => {
+            if let Some(context) = builder.loops.last().copied() {
+                match with {
+                    Some(value) => lower(value, builder),
+                    None => builder.stack_padding(),
+                }
+                builder.unwind_to(context.temporaries, context.scope_depth, true);
+                builder.emit(vm::Instruction::JumpTo(context.end));
+            } else {
+                builder.errors.push(make_error!(
+                    "'break' outside of a loop",
+                    codes::E0015.0,
+                    Severity::Error,
+                    "cannot break out of this" => expression.span
+                ));
+            }
+        },
+        Expr::Continue => {
+            if let Some(context) = builder.loops.last().copied() {
+                builder.unwind_to(context.temporaries, context.scope_depth, false);
+                builder.emit(vm::Instruction::JumpTo(context.start));
+            } else {
+                builder.errors.push(make_error!(
+                    "'continue' outside of a loop",
+                    codes::E0015.0,
+                    Severity::Error,
+                    "cannot continue from here" => expression.span
+                ));
+            }
+        },
+        Expr::Return { with } => {
+            if builder.in_function {
+                match with {
+                    Some(value) => lower(value, builder),
+                    None => builder.stack_padding(),
+                }
+                // returning cleans up the function's part of the stack and its scopes
+                builder.emit(vm::Instruction::Return);
+            } else {
+                builder.errors.push(make_error!(
+                    "'return' outside of a function",
+                    codes::E0016.0,
+                    Severity::Error,
+                    "cannot return from here" => expression.span
+                ));
+            }
+        },
         Expr::Use { .. } => todo!(),
         Expr::Loop { inside } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("loop");
             // builder.emit(vm::Instruction::NewScope);
             let target = builder.target();
+            let end = builder.reserve_target();
+            builder.loops.push(LoopContext { start: target, end: end.0, temporaries: builder.temporaries, scope_depth: builder.scope_depth });
             lower(inside, builder);
+            builder.loops.pop();
             builder.emit(vm::Instruction::Discard);
             builder.emit(vm::Instruction::JumpTo(target));
-            builder.stack_padding();
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
+            // the only way out of a loop is a `break`, which leaves the loop's value on the stack
+            builder.add_reserved(end);
             // builder.emit(vm::Instruction::EndScope);
         },
         Expr::If { condition, then, else_ } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("if");
             lower(condition, builder);
             if let Some(else_) = else_ {
                 let target = builder.reserve_target();
@@ -428,115 +509,337 @@ pub fn lower(expression: &Expression, builder: &mut ProgramBuilder) {
                 // builder.add_reserved(end);
                 builder.stack_padding();
             }
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
         },
         Expr::Assign { left, right } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("assign");
-            if let Expr::Identifier { ref id } = left.et {
-                lower(right, builder);
-                let name_index = builder.add_name(id);
-                builder.emit(vm::Instruction::Store(name_index));
-                builder.stack_padding()
+            match &left.et {
+                Expr::Identifier { id, local } => {
+                    lower(right, builder);
+                    store_variable(id, local, builder);
+                },
+                Expr::Property { object, name } => {
+                    lower(object, builder);
+                    builder.temporaries += 1;
+                    lower(right, builder);
+                    builder.temporaries -= 1;
+                    let name_index = builder.add_name(name);
+                    builder.emit(vm::Instruction::StoreProperty(name_index));
+                },
+                _ => report_bad_assignment(left, builder),
             }
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
+            builder.stack_padding();
         },
         Expr::AssignOp { left, right, op } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("assign_op");
-            if let Expr::Identifier { ref id } = left.et {
-                let name_index = builder.add_name(id);
-                builder.emit(vm::Instruction::LoadVar(name_index));
-                lower(right, builder);
-                builder.emit(match op {
-                    BinaryOp::Add => vm::Instruction::Add,
-                    BinaryOp::Sub => vm::Instruction::Subtract,
-                    BinaryOp::Mul => vm::Instruction::Multiply,
-                    BinaryOp::Div => vm::Instruction::Divide,
-                    BinaryOp::Exp => vm::Instruction::RaiseTo,
-                    BinaryOp::Eq  => vm::Instruction::CheckEquality,
-                    BinaryOp::Lt  => vm::Instruction::Lesser,
-                    BinaryOp::Gt  => vm::Instruction::Greater,
-                    BinaryOp::Leq => vm::Instruction::LesserEq,
-                    BinaryOp::Geq => vm::Instruction::GreaterEq,
-                    BinaryOp::Neq => vm::Instruction::CheckInequality,
-                });
-                builder.emit(vm::Instruction::Store(name_index));
-                builder.stack_padding()
-            }
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
-        },
-        Expr::Identifier { id } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("identifier");
-            let name_index = builder.add_name(id);
-            builder.emit(vm::Instruction::LoadVar(name_index));
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
-        },
-        Expr::Fn { name, body, args } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src(&format!("fn_decl:{}", name));
-            let function_start = builder.reserve_target();
-            let mut function_target = ProgramBuilder::new(builder.debug_instructions);
-            #[cfg(feature = "instruction_sources")]
-            function_target.start_src(&format!("fn:{}", name));
-            if builder.debug_instructions {
-                let name_index = function_target.add_name(name);
-                function_target.emit(vm::Instruction::FunctionTag(name_index));
-            }
-            function_target.emit(vm::Instruction::NewScope);
-            function_target.emit(vm::Instruction::RequireArguments(args.len()));
-            for arg in args.iter().rev() {
-                let name_index = function_target.add_name(arg);
-                function_target.emit(vm::Instruction::AssignStore(name_index))
+            let operation = match op {
+                BinaryOp::Add => vm::Instruction::Add,
+                BinaryOp::Sub => vm::Instruction::Subtract,
+                BinaryOp::Mul => vm::Instruction::Multiply,
+                BinaryOp::Div => vm::Instruction::Divide,
+                BinaryOp::Exp => vm::Instruction::RaiseTo,
+                BinaryOp::Eq  => vm::Instruction::CheckEquality,
+                BinaryOp::Lt  => vm::Instruction::Lesser,
+                BinaryOp::Gt  => vm::Instruction::Greater,
+                BinaryOp::Leq => vm::Instruction::LesserEq,
+                BinaryOp::Geq => vm::Instruction::GreaterEq,
+                BinaryOp::Neq => vm::Instruction::CheckInequality,
+            };
+            match &left.et {
+                Expr::Identifier { id, local } => {
+                    load_variable(id, local, builder);
+                    builder.temporaries += 1;
+                    lower(right, builder);
+                    builder.temporaries -= 1;
+                    builder.emit(operation);
+                    store_variable(id, local, builder);
+                },
+                Expr::Property { object, name } => {
+                    // the object is evaluated once, and kept under the current value for the store
+                    lower(object, builder);
+                    builder.emit(vm::Instruction::Duplicate);
+                    let name_index = builder.add_name(name);
+                    builder.emit(vm::Instruction::AccessProperty(name_index));
+                    builder.temporaries += 2;
+                    lower(right, builder);
+                    builder.temporaries -= 2;
+                    builder.emit(operation);
+                    builder.emit(vm::Instruction::StoreProperty(name_index));
+                },
+                _ => report_bad_assignment(left, builder),
             }
-            lower(body, &mut function_target);
-            function_target.emit(vm::Instruction::EndScope);
-            function_target.emit(vm::Instruction::Swap);
-            function_target.emit(vm::Instruction::PopJump);
-            #[cfg(feature = "instruction_sources")]
-            function_target.end_src();
-            let function = builder.add_constant(vm::Value::Fn(function_start.0));
-            builder.emit(vm::Instruction::LoadConst(function));
+            builder.stack_padding();
+        },
+        Expr::Identifier { id, local } => {
+            load_variable(id, local, builder);
+        },
+        Expr::Fn { name, body, args, slot, .. } => {
+            lower_function(name, args, body, builder);
             builder.emit(vm::Instruction::Duplicate);
             let name_index = builder.add_name(name);
-            builder.emit(vm::Instruction::AssignStore(name_index));
-            builder.end_stuff.push((Some(vm::Instruction::JumpTarget(function_start.0)), function_target));
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
-        },
-        Expr::Let { name, value } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("let");
-            if let Some(x) = value {
-                lower(x, builder);
-                let name_index = builder.add_name(name);
-                builder.emit(vm::Instruction::AssignStore(name_index));
-                builder.stack_padding()
+            builder.emit(vm::Instruction::DeclareLocal(*slot, name_index));
+        },
+        Expr::Lambda { body, args } => {
+            lower_function("<lambda>", args, body, builder);
+        },
+        Expr::Let { name, value, slot, .. } => {
+            match value {
+                Some(x) => lower(x, builder),
+                None => builder.stack_padding(),
             }
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
+            let name_index = builder.add_name(name);
+            builder.emit(vm::Instruction::DeclareLocal(*slot, name_index));
+            builder.stack_padding();
         },
         Expr::While { condition, body } => {
-            #[cfg(feature = "instruction_sources")]
-            builder.start_src("while");
             let target = builder.target();
             let end = builder.reserve_target();
+            let after_padding = builder.reserve_target();
             lower(condition, builder);
             builder.emit(vm::Instruction::Invert);
             builder.emit(vm::Instruction::ConditionalJumpTo(end.0));
+            builder.loops.push(LoopContext { start: target, end: after_padding.0, temporaries: builder.temporaries, scope_depth: builder.scope_depth });
             lower(body, builder);
+            builder.loops.pop();
             builder.emit(vm::Instruction::Discard);
             builder.emit(vm::Instruction::JumpTo(target));
             builder.add_reserved(end);
             builder.stack_padding();
-            #[cfg(feature = "instruction_sources")]
-            builder.end_src();
+            builder.add_reserved(after_padding);
+        },
+        Expr::Match { value, arms } => {
+            let end = builder.reserve_target();
+            lower(value, builder);
+            for arm in arms {
+                let next_arm = builder.reserve_target();
+                if !is_irrefutable(&arm.pattern) {
+                    builder.emit(vm::Instruction::Duplicate);
+                    lower_pattern_test(&arm.pattern, builder);
+                    builder.emit(vm::Instruction::Invert);
+                    builder.emit(vm::Instruction::ConditionalJumpTo(next_arm.0));
+                }
+                builder.open_scope();
+                lower_pattern_bindings(&arm.pattern, builder);
+                lower(&arm.body, builder);
+                builder.close_scope(vm::Instruction::EndScope);
+                builder.emit(vm::Instruction::JumpTo(end.0));
+                builder.add_reserved(next_arm);
+            }
+            builder.emit(vm::Instruction::MatchFailed);
+            builder.add_reserved(end);
         },
         _ => todo!()
     }
-}
\ No newline at end of file
+}
+
+/// number literals that inference couldn't give a type, like the ones compared with a dynamic value,
+/// take the type of the other operand at runtime
+fn is_untyped_number(src: &TokenType, ty: TypeId, builder: &ProgramBuilder) -> bool {
+    matches!(src, TokenType::ILiteral { .. } | TokenType::FLiteral { .. })
+        && matches!(builder.types.info(ty), TypeInfo::Dynamic | TypeInfo::Number | TypeInfo::Integer(None) | TypeInfo::Float(None))
+}
+
+/// `is_untyped_number` for a literal expression, which can be negative
+fn is_untyped_literal(expr: &Expression, builder: &ProgramBuilder) -> bool {
+    match &expr.et {
+        Expr::Literal { src } => is_untyped_number(src, expr.ty, builder),
+        Expr::Unary { op: UnaryOp::Neg, right } => matches!(&right.et, Expr::Literal { src } if is_untyped_number(src, right.ty, builder)),
+        _ => false,
+    }
+}
+
+/// numbers get the type inference found for them, literals that are only used dynamically get `i32` or `f64`.
+/// `negative` is applied before that, so the most negative value of a type fits.
+/// a number that doesn't fit in its type is reported at `span`
+fn literal_value(src: &TokenType, ty: TypeId, negative: bool, span: Span, builder: &mut ProgramBuilder) -> vm::Value {
+    let ty = builder.types.reconstruct(ty).unwrap_or(Type::Dynamic);
+    let sign = if negative { -1 } else { 1 };
+    let number = match src {
+        TokenType::ILiteral { value, kind, .. } => {
+            let radix = match kind {
+                IntLiteralType::Hexadecimal => 16,
+                IntLiteralType::Decimal => 10,
+                IntLiteralType::Octal => 8,
+                IntLiteralType::Binary => 2,
+            };
+            // the lexer only lets through digits of the right radix, so this only fails when there are too many of them
+            let magnitude = u128::from_str_radix(value, radix).ok();
+            match ty.float() {
+                Some(float) => float_literal(magnitude.map_or(f64::INFINITY, |magnitude| sign as f64 * magnitude as f64), float),
+                None => {
+                    let integer = ty.integer().unwrap_or(Integer::I32);
+                    let number = match (magnitude, negative) {
+                        (Some(magnitude), false) => vm::Number::from_unsigned(magnitude, integer, vm::Overflow::Checked),
+                        // `i128::MIN` is the only negative number whose magnitude doesn't fit in an `i128`
+                        (Some(magnitude), true) => 0i128.checked_sub_unsigned(magnitude).and_then(|value| vm::Number::from_integer(value, integer, vm::Overflow::Checked)),
+                        (None, _) => None,
+                    };
+                    number.ok_or_else(|| {
+                        let (min, max) = integer.range();
+                        format!("{}, which goes from {} to {}", integer.name(), min, max)
+                    })
+                },
+            }
+        },
+        TokenType::FLiteral { value, .. } => {
+            let value = sign as f64 * value.parse::<f64>().unwrap_or_else(|_| ice!("the lexer only makes float literals out of digits and a dot"));
+            float_literal(value, ty.float().unwrap_or(Float::F64))
+        },
+        TokenType::SLiteral { value, .. } => return vm::Value::Str(value.clone()),
+        TokenType::BLiteral(val) => return vm::Value::Bool(*val),
+        _ => ice!("literal is not a literal")
+    };
+    match number {
+        Ok(number) => vm::Value::Num(number),
+        Err(r#type) => {
+            let label = format!("this doesn't fit in {}", r#type);
+            builder.errors.push(make_error!(
+                "literal out of range",
+                codes::E0025.0,
+                Severity::Error,
+                label.as_str() => span
+            ));
+            vm::Value::Nothing
+        },
+    }
+}
+
+fn float_literal(value: f64, float: Float) -> Result<vm::Number, String> {
+    match vm::Number::from_float(value, float) {
+        vm::Number::F32(x) if x.is_infinite() => Err(format!("f32, which goes up to {}", f32::MAX)),
+        vm::Number::F64(x) if x.is_infinite() => Err(format!("f64, which goes up to {}", f64::MAX)),
+        number => Ok(number),
+    }
+}
+
+fn is_irrefutable(pattern: &Pattern) -> bool {
+    match &pattern.pt {
+        Pat::Literal { .. } | Pat::Variant { .. } => false,
+        Pat::Wildcard | Pat::Binding { .. } => true,
+    }
+}
+
+/// consumes the value on top of the stack and pushes whether it matches `pattern`
+fn lower_pattern_test(pattern: &Pattern, builder: &mut ProgramBuilder) {
+    match &pattern.pt {
+        Pat::Literal { src, negative, ty } => {
+            let value = literal_value(src, *ty, *negative, pattern.span, builder);
+            let constant = builder.add_constant(value);
+            builder.emit(vm::Instruction::LoadConst(constant));
+            if is_untyped_number(src, *ty, builder) {
+                builder.emit(vm::Instruction::ConvertLiteral(0));
+            }
+            builder.emit(vm::Instruction::CheckEquality);
+        },
+        Pat::Wildcard | Pat::Binding { .. } => {
+            builder.emit(vm::Instruction::Discard);
+            let constant = builder.add_constant(vm::Value::Bool(true));
+            builder.emit(vm::Instruction::LoadConst(constant));
+        },
+        Pat::Variant { path, payload } => {
+            let fail = builder.reserve_target();
+            let done = builder.reserve_target();
+            builder.emit(vm::Instruction::Duplicate);
+            lower(path, builder);
+            builder.emit(vm::Instruction::IsVariant);
+            builder.emit(vm::Instruction::Invert);
+            builder.emit(vm::Instruction::ConditionalJumpTo(fail.0));
+            for (access, item) in payload_accesses(payload, builder) {
+                if is_irrefutable(item) {
+                    continue
+                }
+                builder.emit(vm::Instruction::Duplicate);
+                builder.emit(access);
+                lower_pattern_test(item, builder);
+                builder.emit(vm::Instruction::Invert);
+                builder.emit(vm::Instruction::ConditionalJumpTo(fail.0));
+            }
+            let constant = builder.add_constant(vm::Value::Bool(true));
+            builder.emit(vm::Instruction::Discard);
+            builder.emit(vm::Instruction::LoadConst(constant));
+            builder.emit(vm::Instruction::JumpTo(done.0));
+            builder.add_reserved(fail);
+            let constant = builder.add_constant(vm::Value::Bool(false));
+            builder.emit(vm::Instruction::Discard);
+            builder.emit(vm::Instruction::LoadConst(constant));
+            builder.add_reserved(done);
+        },
+    }
+}
+
+/// consumes the value on top of the stack, declaring the names `pattern` binds in the current scope
+fn lower_pattern_bindings(pattern: &Pattern, builder: &mut ProgramBuilder) {
+    match &pattern.pt {
+        Pat::Binding { name, slot } => {
+            let name_index = builder.add_name(name);
+            builder.emit(vm::Instruction::DeclareLocal(*slot, name_index));
+        },
+        Pat::Literal { .. } | Pat::Wildcard => builder.emit(vm::Instruction::Discard),
+        Pat::Variant { payload, .. } => {
+            for (access, item) in payload_accesses(payload, builder) {
+                builder.emit(vm::Instruction::Duplicate);
+                builder.emit(access);
+                lower_pattern_bindings(item, builder);
+            }
+            builder.emit(vm::Instruction::Discard);
+        },
+    }
+}
+
+/// pushes the value of a variable, by slot when the resolver found where it's declared
+fn load_variable(name: &String, local: &Option<Local>, builder: &mut ProgramBuilder) {
+    let instruction = match local {
+        Some(Local { depth, slot }) => vm::Instruction::LoadLocal(*depth, *slot),
+        None => vm::Instruction::LoadVar(builder.add_name(name)),
+    };
+    builder.emit(instruction)
+}
+
+/// consumes the value on top of the stack, storing it in an already declared variable
+fn store_variable(name: &String, local: &Option<Local>, builder: &mut ProgramBuilder) {
+    let instruction = match local {
+        Some(Local { depth, slot }) => vm::Instruction::StoreLocal(*depth, *slot),
+        None => vm::Instruction::Store(builder.add_name(name)),
+    };
+    builder.emit(instruction)
+}
+
+fn report_bad_assignment(target: &Expression, builder: &mut ProgramBuilder) {
+    builder.errors.push(make_error!(
+        "invalid assignment target",
+        codes::E0020.0,
+        Severity::Error,
+        "only variables and properties (like `a.b.c`) can be assigned to" => target.span
+    ));
+}
+
+/// pairs each subpattern of a variant pattern with the instruction that extracts the value it applies to
+fn payload_accesses<'a>(payload: &'a PatPayload, builder: &mut ProgramBuilder) -> Vec<(vm::Instruction, &'a Pattern)> {
+    match payload {
+        PatPayload::Unit => vec![],
+        PatPayload::Tuple(items) => items.iter().enumerate().map(|(index, item)| (vm::Instruction::PayloadItem(index), item)).collect(),
+        PatPayload::Struct(fields) => fields.iter().map(|(name, item)| (vm::Instruction::AccessProperty(builder.add_name(name)), item)).collect(),
+    }
+}
+
+/// compiles a function body separately and leaves a closure over the current scopes on the stack
+fn lower_function(name: &str, args: &[String], body: &Expression, builder: &mut ProgramBuilder) {
+    let function_start = builder.reserve_target();
+    let mut function_target = builder.for_function();
+    // the prologue and epilogue belong to the function expression itself
+    function_target.span_stack.extend(builder.span_stack.last().copied());
+    if builder.debug_instructions {
+        let name_index = function_target.add_name(&name.to_owned());
+        function_target.emit(vm::Instruction::FunctionTag(name_index));
+    }
+    function_target.emit(vm::Instruction::NewScope);
+    function_target.emit(vm::Instruction::RequireArguments(args.len()));
+    for (slot, arg) in args.iter().enumerate().rev() {
+        let name_index = function_target.add_name(arg);
+        function_target.emit(vm::Instruction::DeclareLocal(slot, name_index))
+    }
+    function_target.in_function = true;
+    lower(body, &mut function_target);
+    function_target.emit(vm::Instruction::Return);
+    let function = builder.add_constant(vm::Value::Fn(function_start.0));
+    builder.emit(vm::Instruction::LoadConst(function));
+    builder.emit(vm::Instruction::MakeClosure);
+    builder.end_stuff.push((Some(vm::Instruction::JumpTarget(function_start.0)), function_target));
+}
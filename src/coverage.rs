@@ -0,0 +1,133 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs,
+    path::Path,
+};
+
+use crate::{
+    engine, errors,
+    failure::Failure,
+    loader::{self, Loader},
+    vm::{Coverage, Instruction, VM},
+};
+
+/// what ran in one file. lines start at 1 like they're shown
+#[derive(Debug, Default)]
+struct FileCoverage {
+    lines: BTreeMap<usize, u64>,
+    /// (line, times jumped, times fell through), `None` if the branch was never reached
+    branches: Vec<(usize, Option<(u64, u64)>)>,
+}
+
+/// runs a file with coverage on, then prints which lines and branches ran.
+/// with `lcov`, the same is also written there as an LCOV tracefile
+pub fn coverage_file<P: AsRef<Path>>(path: P, lcov: Option<&Path>) -> Result<(), Failure> {
+    let mut loader = Loader::new();
+    let file = loader.load_file(path)?;
+    let program = match engine::compile(&mut loader, file, &[]) {
+        Ok((program, _)) => program,
+        Err(errors) => {
+            for error in errors.iter() {
+                errors::print_error(error, &loader);
+            }
+            return Ok(())
+        },
+    };
+    let mut vm = VM::new(program);
+    vm.enable_coverage();
+    if let Err(error) = vm.run() {
+        errors::print_error(&error.to_error(), &loader);
+    }
+    let Some(coverage) = vm.take_coverage() else {
+        return Ok(())
+    };
+    let files = by_file(&coverage, &loader);
+    print_summary(&files, &loader);
+    if let Some(lcov) = lcov {
+        fs::write(lcov, tracefile(&files, &loader)).map_err(Failure::IOError)?;
+    }
+    Ok(())
+}
+
+fn by_file(coverage: &Coverage, loader: &Loader) -> BTreeMap<usize, FileCoverage> {
+    let program = coverage.program();
+    let mut line_starts = HashMap::new();
+    let mut files = BTreeMap::<usize, FileCoverage>::new();
+    for (ip, (span, hits)) in program.spans.iter().zip(coverage.hits.iter()).enumerate() {
+        let Some(span) = span else {
+            continue
+        };
+        let starts = line_starts.entry(span.file).or_insert_with(|| loader.get_file(span.file).line_starts());
+        let line = loader::line_of(starts, span.start) + 1;
+        let file = files.entry(span.file).or_default();
+        if let Instruction::ConditionalJump(_) = program.instructions[ip] {
+            file.branches.push((line, coverage.branches.get(&ip).copied()));
+        }
+        // the spans of blocks and functions cover every line in them, which would make those lines look run
+        if loader::line_of(starts, span.end) + 1 == line {
+            let count = file.lines.entry(line).or_default();
+            *count = (*count).max(*hits);
+        }
+    }
+    // functions are compiled after the code around them
+    for file in files.values_mut() {
+        file.branches.sort_by_key(|(line, _)| *line);
+    }
+    files
+}
+
+fn print_summary(files: &BTreeMap<usize, FileCoverage>, loader: &Loader) {
+    for (file, coverage) in files {
+        let lines_hit = coverage.lines.values().filter(|hits| **hits > 0).count();
+        // each conditional jump is two branches, taken and not taken
+        let branches_hit = coverage.branches.iter().map(|(_, counts)| counts.map_or(0, |(jumped, fell_through)| (jumped > 0) as usize + (fell_through > 0) as usize)).sum::<usize>();
+        eprintln!("\x1B[32m{}\x1B[0m", loader.get_file(*file).src().unwrap_or("<unknown>"));
+        eprintln!("    lines    {:>5}/{:<5} {}", lines_hit, coverage.lines.len(), percent(lines_hit, coverage.lines.len()));
+        eprintln!("    branches {:>5}/{:<5} {}", branches_hit, coverage.branches.len() * 2, percent(branches_hit, coverage.branches.len() * 2));
+        let missed = coverage.lines.iter().filter(|(_, hits)| **hits == 0).map(|(line, _)| line.to_string()).collect::<Vec<_>>();
+        if !missed.is_empty() {
+            eprintln!("    never ran: {}", missed.join(", "));
+        }
+    }
+}
+
+fn percent(hit: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".to_owned()
+    }
+    format!("{:.1}%", hit as f64 * 100.0 / total as f64)
+}
+
+/// https://github.com/linux-test-project/lcov/blob/master/man/geninfo.1 describes the format.
+/// each conditional jump is a block, branch 0 being the condition holding and 1 the jump past it
+fn tracefile(files: &BTreeMap<usize, FileCoverage>, loader: &Loader) -> String {
+    let mut out = String::new();
+    for (file, coverage) in files {
+        let _ = writeln!(out, "TN:");
+        let _ = writeln!(out, "SF:{}", loader.get_file(*file).src().unwrap_or("<unknown>"));
+        let mut branches_hit = 0;
+        for (block, (line, counts)) in coverage.branches.iter().enumerate() {
+            match counts {
+                Some((jumped, fell_through)) => {
+                    branches_hit += (*jumped > 0) as usize + (*fell_through > 0) as usize;
+                    let _ = writeln!(out, "BRDA:{},{},0,{}", line, block, fell_through);
+                    let _ = writeln!(out, "BRDA:{},{},1,{}", line, block, jumped);
+                },
+                None => {
+                    let _ = writeln!(out, "BRDA:{},{},0,-", line, block);
+                    let _ = writeln!(out, "BRDA:{},{},1,-", line, block);
+                },
+            }
+        }
+        let _ = writeln!(out, "BRF:{}", coverage.branches.len() * 2);
+        let _ = writeln!(out, "BRH:{}", branches_hit);
+        for (line, hits) in coverage.lines.iter() {
+            let _ = writeln!(out, "DA:{},{}", line, hits);
+        }
+        let _ = writeln!(out, "LF:{}", coverage.lines.len());
+        let _ = writeln!(out, "LH:{}", coverage.lines.values().filter(|hits| **hits > 0).count());
+        let _ = writeln!(out, "end_of_record");
+    }
+    out
+}
@@ -0,0 +1,365 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use serde_json::{json, Value as Json};
+
+use crate::{
+    engine,
+    errors::{Error, Severity},
+    loader::{self, Loader, Span},
+    vm::{self, DebugCommand, Debugger, Scope, Value, VM},
+};
+
+/// serves the Debug Adapter Protocol until the client disconnects, running one program under `vm::Debugger`.
+/// requests are only read while the program is paused, or before and after it runs
+pub fn serve<R: BufRead, W: Write>(input: R, output: W) -> io::Result<()> {
+    let mut adapter = Adapter {
+        connection: Connection { input, output, seq: 0 },
+        loader: Loader::new(),
+        breakpoints: HashMap::new(),
+        paths: HashMap::new(),
+        line_starts: HashMap::new(),
+        references: vec![],
+        command: None,
+        disconnected: false,
+        error: None,
+    };
+    let mut launched = None;
+    loop {
+        let Some(request) = adapter.connection.read()? else {
+            return Ok(())
+        };
+        match command_of(&request) {
+            "initialize" => {
+                adapter.connection.respond(&request, json!({ "supportsConfigurationDoneRequest": true }))?;
+                adapter.connection.event("initialized", json!({}))?;
+            },
+            "launch" => match adapter.launch(&request["arguments"]) {
+                Ok(launch) => {
+                    launched = Some(launch);
+                    adapter.connection.respond(&request, json!({}))?;
+                },
+                Err(message) => adapter.connection.fail(&request, &message)?,
+            },
+            "configurationDone" => {
+                adapter.connection.respond(&request, json!({}))?;
+                if launched.is_some() {
+                    break
+                }
+            },
+            "disconnect" => return adapter.connection.respond(&request, json!({})),
+            _ => adapter.handle(&request, None)?,
+        }
+    }
+    let Some((program, stop_on_entry)) = launched else {
+        return Ok(())
+    };
+    let mut vm = VM::new(program);
+    let command = if stop_on_entry { DebugCommand::StepInto } else { DebugCommand::Continue };
+    let exit_code = match vm.debug(&mut adapter, command) {
+        Ok(_) => 0,
+        Err(error) => {
+            let message = describe(&error.to_error(), &adapter.loader);
+            adapter.connection.event("output", json!({ "category": "stderr", "output": message + "\n" }))?;
+            1
+        },
+    };
+    if let Some(error) = adapter.error.take() {
+        return Err(error)
+    }
+    adapter.connection.event("exited", json!({ "exitCode": exit_code }))?;
+    adapter.connection.event("terminated", json!({}))?;
+    if adapter.disconnected {
+        return Ok(())
+    }
+    while let Some(request) = adapter.connection.read()? {
+        if command_of(&request) == "disconnect" {
+            return adapter.connection.respond(&request, json!({}))
+        }
+        adapter.handle(&request, None)?;
+    }
+    Ok(())
+}
+
+fn command_of(request: &Json) -> &str {
+    request["command"].as_str().unwrap_or("")
+}
+
+/// `file:line: error (E0000): message`, without the source excerpts `errors::print_error` shows
+fn describe(error: &Error, loader: &Loader) -> String {
+    let severity = match error.severity {
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+        Severity::FatalError => "fatal error",
+    };
+    let location = error.spans.first().map(|span| {
+        let file = loader.get_file(span.file);
+        let line = loader::line_of(&file.line_starts(), span.start) + 1;
+        format!("{}:{}: ", file.src().unwrap_or("<unknown>"), line)
+    });
+    format!("{}{} ({}): {}", location.unwrap_or_default(), severity, error.code, error.message)
+}
+
+/// `Content-Length` framed JSON messages
+struct Connection<R, W> {
+    input: R,
+    output: W,
+    seq: u64,
+}
+
+impl<R: BufRead, W: Write> Connection<R, W> {
+    /// `None` once the input is closed
+    fn read(&mut self) -> io::Result<Option<Json>> {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if self.input.read_line(&mut header)? == 0 {
+                return Ok(None)
+            }
+            let header = header.trim();
+            if header.is_empty() {
+                break
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+        let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "message without a Content-Length"))?;
+        let mut body = vec![0; length];
+        self.input.read_exact(&mut body)?;
+        serde_json::from_slice(&body).map(Some).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let body = message.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        self.output.flush()
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "response", "request_seq": request["seq"], "command": request["command"], "success": true, "body": body }))
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) -> io::Result<()> {
+        self.send(json!({ "type": "response", "request_seq": request["seq"], "command": request["command"], "success": false, "message": message }))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// something `variables` can look inside of
+enum Reference {
+    Scope(Rc<RefCell<Scope>>),
+    Value(Value),
+}
+
+struct Adapter<R, W> {
+    connection: Connection<R, W>,
+    loader: Loader,
+    /// lines (starting at 1) by canonical path
+    breakpoints: HashMap<PathBuf, HashSet<usize>>,
+    /// the canonical path of each loaded file
+    paths: HashMap<usize, PathBuf>,
+    line_starts: HashMap<usize, Vec<u32>>,
+    /// handed out as `variablesReference`s (off by one, 0 means there's nothing inside), only valid while paused
+    references: Vec<Reference>,
+    /// what the program was last told to do, to say why it stopped
+    command: Option<DebugCommand>,
+    /// the client went away while the program was paused
+    disconnected: bool,
+    /// talking to the client failed, which stops the program the next time it pauses
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead, W: Write> Adapter<R, W> {
+    /// loads and compiles the program, returning it along with whether to pause before its first line
+    fn launch(&mut self, arguments: &Json) -> Result<(vm::Program, bool), String> {
+        let path = arguments["program"].as_str().ok_or("launch needs a program")?;
+        let file = self.loader.load_file(path).map_err(|failure| format!("couldn't read {}: {:?}", path, failure))?;
+        match engine::compile(&mut self.loader, file, &[]) {
+            Ok((program, _)) => Ok((program, arguments["stopOnEntry"].as_bool().unwrap_or(false))),
+            Err(errors) => Err(errors.iter().map(|error| describe(error, &self.loader)).collect::<Vec<_>>().join("\n")),
+        }
+    }
+
+    fn path(&mut self, file: usize) -> &Path {
+        let loader = &self.loader;
+        self.paths.entry(file).or_insert_with(|| {
+            let path = PathBuf::from(loader.get_file(file).src().unwrap_or(""));
+            path.canonicalize().unwrap_or(path)
+        })
+    }
+
+    fn column(&mut self, span: Span) -> u32 {
+        let line = self.line(span);
+        span.start - self.line_starts[&span.file][line - 1] + 1
+    }
+
+    fn reference(&mut self, reference: Reference) -> usize {
+        self.references.push(reference);
+        self.references.len()
+    }
+
+    fn variable(&mut self, name: &str, value: &Value) -> Json {
+        let reference = match value {
+            Value::Scope(scope) => self.reference(Reference::Scope(scope.clone())),
+            Value::Struct(_) | Value::Variant(_) => self.reference(Reference::Value(value.clone())),
+            _ => 0,
+        };
+        json!({ "name": name, "value": vm::display(value), "variablesReference": reference })
+    }
+
+    fn variables(&mut self, reference: usize) -> Vec<Json> {
+        let named = match self.references.get(reference.wrapping_sub(1)) {
+            Some(Reference::Scope(scope)) => {
+                let scope = scope.borrow();
+                scope.names.iter().cloned().zip(scope.slots.iter().cloned()).filter(|(name, _)| !name.is_empty()).collect::<Vec<_>>()
+            },
+            Some(Reference::Value(Value::Struct(instance))) => {
+                let instance = instance.borrow();
+                instance.def.fields.iter().cloned().zip(instance.fields.iter().cloned()).collect()
+            },
+            Some(Reference::Value(Value::Variant(variant))) => match &variant.def.variants[variant.index].fields {
+                vm::VariantFields::Struct(fields) => fields.iter().cloned().zip(variant.payload.iter().cloned()).collect(),
+                _ => variant.payload.iter().cloned().enumerate().map(|(index, value)| (index.to_string(), value)).collect(),
+            },
+            _ => vec![],
+        };
+        named.iter().map(|(name, value)| self.variable(name, value)).collect()
+    }
+
+    /// the requests that can come at any time. `vm` is only there while the program is paused
+    fn handle(&mut self, request: &Json, vm: Option<&VM>) -> io::Result<()> {
+        let arguments = &request["arguments"];
+        match (command_of(request), vm) {
+            ("threads", _) => self.connection.respond(request, json!({ "threads": [{ "id": 1, "name": "main" }] })),
+            ("setBreakpoints", _) => {
+                let path = PathBuf::from(arguments["source"]["path"].as_str().unwrap_or(""));
+                let path = path.canonicalize().unwrap_or(path);
+                let lines = arguments["breakpoints"].as_array().into_iter().flatten().filter_map(|breakpoint| breakpoint["line"].as_u64()).map(|line| line as usize).collect::<Vec<_>>();
+                let verified = lines.iter().map(|line| json!({ "verified": true, "line": line })).collect::<Vec<_>>();
+                self.breakpoints.insert(path, lines.into_iter().collect());
+                self.connection.respond(request, json!({ "breakpoints": verified }))
+            },
+            ("stackTrace", Some(vm)) => {
+                let frames = vm.backtrace().into_iter().enumerate().map(|(id, frame)| {
+                    let name = frame.function.unwrap_or_else(|| "<top level>".to_owned());
+                    match frame.span {
+                        Some(span) => {
+                            let path = self.path(span.file).to_string_lossy().into_owned();
+                            json!({ "id": id, "name": name, "source": { "path": path }, "line": self.line(span), "column": self.column(span) })
+                        },
+                        None => json!({ "id": id, "name": name, "line": 0, "column": 0 }),
+                    }
+                }).collect::<Vec<_>>();
+                let total = frames.len();
+                self.connection.respond(request, json!({ "stackFrames": frames, "totalFrames": total }))
+            },
+            ("scopes", Some(vm)) => {
+                let frame = arguments["frameId"].as_u64().unwrap_or(0) as usize;
+                let scopes = match vm.scopes(frame) {
+                    Some(scopes) if frame < vm.backtrace().len() => scopes.iter().rev().collect::<Vec<_>>(),
+                    _ => return self.connection.fail(request, &format!("there is no frame {}", frame)),
+                };
+                let last = scopes.len().saturating_sub(1);
+                let scopes = scopes.into_iter().enumerate().map(|(depth, scope)| {
+                    let name = match depth {
+                        0 => "Locals".to_owned(),
+                        _ if depth == last => "Top level".to_owned(),
+                        _ => format!("Enclosing scope {}", depth),
+                    };
+                    json!({ "name": name, "variablesReference": self.reference(Reference::Scope(scope.clone())), "expensive": false })
+                }).collect::<Vec<_>>();
+                self.connection.respond(request, json!({ "scopes": scopes }))
+            },
+            ("variables", Some(_)) => {
+                let variables = self.variables(arguments["variablesReference"].as_u64().unwrap_or(0) as usize);
+                self.connection.respond(request, json!({ "variables": variables }))
+            },
+            (command, Some(_)) => self.connection.fail(request, &format!("unsupported request {}", command)),
+            (command, None) => self.connection.fail(request, &format!("{} can only be used while the program is paused", command)),
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> Debugger for Adapter<R, W> {
+    fn line(&mut self, span: Span) -> usize {
+        let loader = &self.loader;
+        let line_starts = self.line_starts.entry(span.file).or_insert_with(|| loader.get_file(span.file).line_starts());
+        loader::line_of(line_starts, span.start) + 1
+    }
+
+    fn has_breakpoint(&mut self, file: usize, line: usize) -> bool {
+        let path = self.path(file).to_owned();
+        self.breakpoints.get(&path).is_some_and(|lines| lines.contains(&line))
+    }
+
+    fn paused(&mut self, vm: &mut VM) -> DebugCommand {
+        self.references.clear();
+        let at_breakpoint = vm.program().spans[vm.ip()].is_some_and(|span| {
+            let line = self.line(span);
+            self.has_breakpoint(span.file, line)
+        });
+        let reason = match self.command {
+            _ if at_breakpoint => "breakpoint",
+            None => "entry",
+            Some(_) => "step",
+        };
+        if self.error.is_some() {
+            return DebugCommand::Stop
+        }
+        // the client going away ends the debuggee too
+        let result = self.connection.event("stopped", json!({ "reason": reason, "threadId": 1, "allThreadsStopped": true })).and_then(|()| loop {
+            let Some(request) = self.connection.read()? else {
+                self.disconnected = true;
+                break Ok(DebugCommand::Stop)
+            };
+            let command = match command_of(&request) {
+                "continue" => DebugCommand::Continue,
+                "next" => DebugCommand::StepOver,
+                "stepIn" => DebugCommand::StepInto,
+                "stepOut" => DebugCommand::StepOut,
+                "disconnect" => {
+                    self.connection.respond(&request, json!({}))?;
+                    self.disconnected = true;
+                    break Ok(DebugCommand::Stop)
+                },
+                _ => {
+                    self.handle(&request, Some(vm))?;
+                    continue
+                },
+            };
+            let body = if command == DebugCommand::Continue { json!({ "allThreadsContinued": true }) } else { json!({}) };
+            self.connection.respond(&request, body)?;
+            break Ok(command)
+        });
+        match result {
+            Ok(command) => {
+                self.command = Some(command);
+                command
+            },
+            Err(error) => {
+                self.error = Some(error);
+                DebugCommand::Stop
+            },
+        }
+    }
+
+    fn show(&mut self, text: &str) {
+        if self.error.is_none() {
+            if let Err(error) = self.connection.event("output", json!({ "category": "stdout", "output": format!("{}\n", text) })) {
+                self.error = Some(error)
+            }
+        }
+    }
+}
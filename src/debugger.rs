@@ -0,0 +1,199 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    io::{self, BufRead, Write},
+    path::Path,
+    rc::Rc,
+};
+
+use crate::{
+    engine, errors,
+    failure::Failure,
+    ice::ice,
+    loader::{self, Loader, Span},
+    vm::{self, DebugCommand, Debugger, Scope, VM},
+};
+
+const HELP: &str = "\
+c, continue        run until a breakpoint
+s, step            run until another line, going into calls
+n, next            run until another line of this function
+o, out             run until this function returns
+b, break [line]    set a breakpoint in the file being debugged, or list them
+d, delete <line>   remove a breakpoint
+bt, backtrace      show the functions that are running
+f, frame <n>       look at the function `n` calls out from this one
+l, list            show the source around the current line
+stack              show the values on the stack
+scopes             show the variables in every scope of the current frame
+p, print <expr>    evaluate an expression in the current frame
+q, quit            stop the program";
+
+/// a debugger driven from stdin, run through `debug_file`
+#[derive(Debug)]
+pub struct CliDebugger {
+    loader: Loader,
+    /// the file being debugged, where breakpoints are set
+    file: usize,
+    /// (file, line), lines start at 1 like they're shown
+    breakpoints: HashSet<(usize, usize)>,
+    line_starts: HashMap<usize, Vec<u32>>,
+    /// which frame `print` and `scopes` look at, 0 is the innermost one
+    frame: usize,
+}
+
+/// runs a file under the debugger, pausing before its first line
+pub fn debug_file<P: AsRef<Path>>(path: P) -> Result<(), Failure> {
+    let mut loader = Loader::new();
+    let file = loader.load_file(path)?;
+    let program = match engine::compile(&mut loader, file, &[]) {
+        Ok((program, warnings)) => {
+            for warning in warnings.iter() {
+                errors::print_error(warning, &loader);
+            }
+            program
+        },
+        Err(errors) => {
+            for error in errors.iter() {
+                errors::print_error(error, &loader);
+            }
+            return Ok(())
+        },
+    };
+    let mut debugger = CliDebugger { loader, file, breakpoints: HashSet::new(), line_starts: HashMap::new(), frame: 0 };
+    let mut vm = VM::new(program);
+    match vm.debug(&mut debugger, DebugCommand::StepInto) {
+        Ok(Some(value)) => eprintln!("finished with {}", vm::display(&value)),
+        Ok(None) => (),
+        Err(error) => errors::print_error(&error.to_error(), &debugger.loader),
+    }
+    Ok(())
+}
+
+impl CliDebugger {
+    fn location(&mut self, span: Span) -> String {
+        let line = self.line(span);
+        format!("{}:{}", self.loader.get_file(span.file).src().unwrap_or("<unknown>"), line)
+    }
+
+    fn show_lines(&mut self, span: Span, around: usize) {
+        let current = self.line(span);
+        let source = self.loader.get_file(span.file).string();
+        for (index, text) in source.lines().enumerate().skip(current.saturating_sub(around + 1)).take(around * 2 + 1) {
+            let marker = if index + 1 == current { ">" } else { " " };
+            eprintln!("{} {:4} | {}", marker, index + 1, text);
+        }
+    }
+
+    fn show_scopes(&self, vm: &VM) {
+        let scopes = vm.scopes(self.frame).unwrap_or_else(|| ice!("frame {} was selected without checking it", self.frame));
+        for (depth, scope) in scopes.iter().rev().enumerate() {
+            let scope = scope.borrow();
+            eprintln!("scope {}:", depth);
+            for (name, value) in scope.names.iter().zip(scope.slots.iter()).filter(|(name, _)| !name.is_empty()) {
+                eprintln!("    {} = {}", name, vm::display(value));
+            }
+        }
+    }
+
+    fn evaluate(&mut self, vm: &VM, src: &str) {
+        let index = self.loader.load_from_memory(src.to_owned(), "<debugger>".to_owned());
+        let mut scopes = vm.scopes(self.frame).unwrap_or_else(|| ice!("frame {} was selected without checking it", self.frame)).to_vec();
+        // what the expression declares goes in a scope of its own, so it can't overwrite the program's slots
+        scopes.push(Rc::new(RefCell::new(Scope::default())));
+        let names = scopes.iter().map(|scope| scope.borrow().names.clone()).collect::<Vec<_>>();
+        let program = match engine::compile(&mut self.loader, index, &names) {
+            Ok((program, _)) => program,
+            Err(errors) => {
+                for error in errors.iter() {
+                    errors::print_error(error, &self.loader);
+                }
+                return
+            },
+        };
+        match VM::with_scopes(program, scopes).run() {
+            Ok(value) => eprintln!("{}", vm::display(&value)),
+            Err(error) => errors::print_error(&error.to_error(), &self.loader),
+        }
+    }
+}
+
+impl Debugger for CliDebugger {
+    fn line(&mut self, span: Span) -> usize {
+        let loader = &self.loader;
+        let line_starts = self.line_starts.entry(span.file).or_insert_with(|| loader.get_file(span.file).line_starts());
+        loader::line_of(line_starts, span.start) + 1
+    }
+
+    fn has_breakpoint(&mut self, file: usize, line: usize) -> bool {
+        self.breakpoints.contains(&(file, line))
+    }
+
+    fn paused(&mut self, vm: &mut VM) -> DebugCommand {
+        self.frame = 0;
+        if let Some(span) = vm.program().spans[vm.ip()] {
+            eprintln!("paused at {}", self.location(span));
+            self.show_lines(span, 0);
+        }
+        let stdin = io::stdin();
+        loop {
+            eprint!("(eeeee) ");
+            let _ = io::stderr().flush();
+            let mut input = String::new();
+            match stdin.lock().read_line(&mut input) {
+                Ok(0) | Err(_) => return DebugCommand::Stop,
+                Ok(_) => (),
+            }
+            let input = input.trim();
+            let (command, argument) = input.split_once(' ').map_or((input, ""), |(command, argument)| (command, argument.trim()));
+            match command {
+                "" => (),
+                "c" | "continue" => return DebugCommand::Continue,
+                "s" | "step" => return DebugCommand::StepInto,
+                "n" | "next" => return DebugCommand::StepOver,
+                "o" | "out" => return DebugCommand::StepOut,
+                "b" | "break" if argument.is_empty() => {
+                    let mut breakpoints = self.breakpoints.iter().map(|(_, line)| *line).collect::<Vec<_>>();
+                    breakpoints.sort_unstable();
+                    for line in breakpoints {
+                        eprintln!("line {}", line);
+                    }
+                },
+                "b" | "break" | "d" | "delete" => match argument.parse::<usize>() {
+                    Ok(line) if command.starts_with('b') => {
+                        self.breakpoints.insert((self.file, line));
+                    },
+                    Ok(line) => {
+                        self.breakpoints.remove(&(self.file, line));
+                    },
+                    Err(_) => eprintln!("expected a line number"),
+                },
+                "bt" | "backtrace" => {
+                    for (depth, frame) in vm.backtrace().into_iter().enumerate() {
+                        let location = frame.span.map_or_else(|| format!("instruction {}", frame.ip), |span| self.location(span));
+                        eprintln!("#{} in {} at {}", depth, frame.function.as_deref().unwrap_or("<top level>"), location);
+                    }
+                },
+                "f" | "frame" => match argument.parse::<usize>() {
+                    Ok(frame) if frame < vm.backtrace().len() => self.frame = frame,
+                    _ => eprintln!("expected a frame number from the backtrace"),
+                },
+                "l" | "list" => {
+                    if let Some(span) = vm.backtrace()[self.frame].span {
+                        self.show_lines(span, 5);
+                    }
+                },
+                "stack" => {
+                    for value in vm.stack().iter().rev() {
+                        eprintln!("{}", vm::display(value));
+                    }
+                },
+                "scopes" => self.show_scopes(vm),
+                "p" | "print" => self.evaluate(vm, argument),
+                "h" | "help" => eprintln!("{}", HELP),
+                "q" | "quit" => return DebugCommand::Stop,
+                _ => eprintln!("unknown command {}, try help", command),
+            }
+        }
+    }
+}
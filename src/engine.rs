@@ -0,0 +1,202 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use crate::{
+    compiler::{self, ProgramBuilder},
+    errors::{Error, Severity},
+    failure::Failure,
+    inference, lexer,
+    loader::Loader,
+    parser, resolver,
+    vm::{self, Limits, NativeFn, Overflow, Program, RuntimeError, RuntimeErrorKind, Scope, Value, VM},
+};
+
+/// runs scripts for a host program. scripts run their top level in the scope of the globals,
+/// so what they declare there can be used by the host and by later scripts
+#[derive(Debug)]
+pub struct Engine {
+    loader: Loader,
+    globals: Rc<RefCell<Scope>>,
+    /// the diagnostics that didn't stop the last script from running
+    warnings: Vec<Error>,
+    /// what each script gets to use, fuel included
+    limits: Limits,
+    overflow: Overflow,
+    /// the script (or call) that hit a limit
+    suspended: Option<VM>,
+}
+
+#[derive(Debug)]
+pub enum EngineError {
+    /// the file couldn't be read
+    Load(Failure),
+    /// everything that was reported before giving up, including runtime errors
+    Diagnostics(Vec<Error>),
+    /// the script was stopped by one of the limits, `Engine::resume` continues it
+    LimitExceeded(RuntimeError),
+    /// `Engine::resume` was called without a stopped script
+    NothingToResume,
+}
+
+impl From<Vec<Error>> for EngineError {
+    fn from(errors: Vec<Error>) -> Self {
+        EngineError::Diagnostics(errors)
+    }
+}
+
+impl From<RuntimeError> for EngineError {
+    fn from(error: RuntimeError) -> Self {
+        EngineError::Diagnostics(vec![error.to_error()])
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine { loader: Loader::new(), globals: Rc::new(RefCell::new(Scope::default())), warnings: vec![], limits: Limits::default(), overflow: Overflow::default(), suspended: None }
+    }
+
+    /// applies to the scripts evaluated from now on, each one starts with all of `limits.fuel`
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits
+    }
+
+    /// what integer arithmetic does when it goes out of range, in the scripts evaluated and functions called from now on
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow
+    }
+
+    /// continues the script that hit a limit with more fuel. if it was another limit, `set_limits` has to raise it first
+    pub fn resume(&mut self, fuel: u64) -> Result<Value, EngineError> {
+        let mut vm = self.suspended.take().ok_or(EngineError::NothingToResume)?;
+        let fuel = vm.limits().fuel.map(|remaining| remaining + fuel);
+        vm.set_limits(Limits { fuel, ..self.limits.clone() });
+        self.execute(vm)
+    }
+
+    /// every source evaluated so far, for printing diagnostics with `errors::print_error`
+    pub fn loader(&self) -> &Loader {
+        &self.loader
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// evaluates `src` and returns the value it evaluated to. `name` is only used in diagnostics
+    pub fn eval(&mut self, src: &str, name: &str) -> Result<Value, EngineError> {
+        let index = self.loader.load_from_memory(src.to_owned(), name.to_owned());
+        self.run(index)
+    }
+
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value, EngineError> {
+        let index = self.loader.load_file(path).map_err(EngineError::Load)?;
+        self.run(index)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().set(name, vm::stored_in(value, &self.globals))
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().get(name).map(|value| vm::loaded_from(value, &self.globals))
+    }
+
+    /// makes `function` available to scripts as the global `name`
+    pub fn register_fn<F: Fn(&[Value]) -> Result<Value, RuntimeError> + 'static>(&mut self, name: &str, function: F) {
+        self.set_global(name, Value::Native(Rc::new(NativeFn::new(name, function))))
+    }
+
+    /// makes `function` available to scripts as `module.name`, creating the module if there's no global called `module` yet
+    pub fn register_module_fn<F: Fn(&[Value]) -> Result<Value, RuntimeError> + 'static>(&mut self, module: &str, name: &str, function: F) {
+        let scope = match self.get_global(module) {
+            Some(Value::Scope(scope)) => scope,
+            _ => {
+                let scope = Rc::new(RefCell::new(Scope::default()));
+                self.set_global(module, Value::Scope(scope.clone()));
+                scope
+            },
+        };
+        let function = NativeFn::new(&format!("{}.{}", module, name), function);
+        scope.borrow_mut().set(name, Value::Native(Rc::new(function)));
+    }
+
+    /// calls the global function `name`, under the same limits as a script
+    pub fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, EngineError> {
+        let function = self.get_global(name).ok_or_else(|| RuntimeError { kind: RuntimeErrorKind::UndefinedVariable(name.to_owned()), trace: vec![] })?;
+        self.suspended = None;
+        let mut vm = VM::with_call(function, arguments);
+        vm.set_limits(self.limits.clone());
+        vm.set_overflow(self.overflow);
+        self.execute(vm)
+    }
+
+    fn run(&mut self, index: usize) -> Result<Value, EngineError> {
+        self.warnings.clear();
+        self.suspended = None;
+        let globals = self.globals.borrow().names.clone();
+        let (program, warnings) = compile(&mut self.loader, index, &[globals])?;
+        self.warnings = warnings;
+        let mut vm = VM::with_scopes(program, vec![self.globals.clone()]);
+        vm.set_limits(self.limits.clone());
+        vm.set_overflow(self.overflow);
+        self.execute(vm)
+    }
+
+    fn execute(&mut self, mut vm: VM) -> Result<Value, EngineError> {
+        match vm.run() {
+            Ok(value) => Ok(value),
+            Err(error) if matches!(error.kind, RuntimeErrorKind::LimitExceeded(_)) => {
+                self.suspended = Some(vm);
+                Err(EngineError::LimitExceeded(error))
+            },
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// takes a loaded file all the way to a program that can run under `scopes`, which are the names
+/// each scope declares by slot, innermost last. returns the warnings along with it
+pub fn compile(loader: &mut Loader, index: usize, scopes: &[Vec<String>]) -> Result<(Program, Vec<Error>), Vec<Error>> {
+    let (tokens, result) = lexer::lex(loader.get_file(index));
+    let mut diagnostics = result.err().unwrap_or_default();
+    if diagnostics.iter().any(Error::fatal) {
+        return Err(diagnostics)
+    }
+    let mut expr = match parser::parse(&tokens, index, loader) {
+        Ok(expr) => expr,
+        Err(errors) => {
+            diagnostics.extend(errors);
+            return Err(diagnostics)
+        },
+    };
+    if let Err(errors) = resolver::resolve_in_scopes(&mut expr, scopes) {
+        diagnostics.extend(errors);
+        return Err(diagnostics)
+    }
+    let types = match inference::infer_in_scopes(&mut expr, scopes) {
+        Ok(types) => types,
+        Err(errors) => {
+            diagnostics.extend(errors);
+            return Err(diagnostics)
+        },
+    };
+    let mut builder = ProgramBuilder::new(true, types);
+    compiler::lower(&expr, &mut builder);
+    let program = match builder.finish() {
+        Ok(program) => program,
+        Err(errors) => {
+            diagnostics.extend(errors);
+            return Err(diagnostics)
+        },
+    };
+    // the lexer recovers from plain errors, but the program shouldn't run with them
+    if diagnostics.iter().any(|error| error.severity == Severity::Error) {
+        return Err(diagnostics)
+    }
+    Ok((program, diagnostics))
+}
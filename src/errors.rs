@@ -50,6 +50,18 @@ pub mod codes {
     );
     d!(E0013, "todo: add explanation for this error (functions require names)");
     d!(E0014, "todo: add explanation for this error (failed to find file with include)");
+    d!(E0015, "todo: add explanation for this error (break/continue outside of a loop)");
+    d!(E0016, "todo: add explanation for this error (return outside of a function)");
+    d!(E0017, "todo: add explanation for this error (ambiguous or misplaced _ placeholder)");
+    d!(E0018, "todo: add explanation for this error (expected a pattern)");
+    d!(E0019, "todo: add explanation for this error (field declared more than once)");
+    d!(E0020, "todo: add explanation for this error (invalid assignment target)");
+    d!(E0021, "todo: add explanation for this error (assignment to an undeclared variable)");
+    d!(E0022, "todo: add explanation for this error (runtime error)");
+    d!(E0023, "todo: add explanation for this error (mismatched types)");
+    d!(E0024, "todo: add explanation for this error (unknown type)");
+    d!(E0025, "todo: add explanation for this error (literal out of range)");
+    d!(E0026, "todo: add explanation for this error (invalid struct literal)");
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -283,9 +295,7 @@ pub fn print_error(error: &Error, sources: &Loader) {
                     &line_end[..]
                 },
             );
-            for (_, (_, (_, start_col), (end_line, end_col), one_liner, original_index)) in
-                relevant_spans.iter().enumerate()
-            {
+            for (_, (_, start_col), (end_line, end_col), one_liner, original_index) in relevant_spans.iter() {
                 // let span = *span;
                 let end_line = *end_line;
                 let start_col = *start_col;
@@ -0,0 +1,616 @@
+use std::{collections::HashSet, rc::Rc};
+
+use crate::{
+    ast::{self, Expression, Expr, EnumPayload, BinaryOp, UnaryOp, Pattern, Pat, PatPayload, Type, TypeKind, stringify_type},
+    errors::{codes, make_error, Error, Severity},
+    ice::ice,
+    loader::Span,
+    tokens::{FLiteralTypeHint, ILiteralTypeHint, TokenType},
+    typing::{Float, Integer, Machine, TypeId, TypeInfo},
+};
+
+/// gives every expression a type, unifying them the way they are used.
+/// anything reached through a module, a struct or a name the resolver couldn't bind is dynamic,
+/// and is only checked at runtime. has to run after the resolver, whose scopes it follows
+pub fn infer(expr: &mut Expression) -> Result<Machine, Vec<Error>> {
+    infer_in_scopes(expr, &[])
+}
+
+/// like `infer`, for a program resolved with `resolver::resolve_in_scopes`. what the scopes already declare is dynamic
+pub fn infer_in_scopes(expr: &mut Expression, scopes: &[Vec<String>]) -> Result<Machine, Vec<Error>> {
+    // the scopes can hold types declared by an earlier program
+    let mut declared_types = scopes.iter().flatten().cloned().collect();
+    collect_declared_types(expr, &mut declared_types);
+    let mut inference = Inference { machine: Machine::default(), scopes: vec![], returns: vec![], loops: vec![], declared_types, errors: vec![] };
+    for names in scopes.iter() {
+        let bindings = names.iter().map(|_| Binding { ty: inference.machine.insert(TypeInfo::Dynamic), generic: vec![], declaration: None }).collect();
+        inference.scopes.push(bindings);
+    }
+    if inference.scopes.is_empty() {
+        inference.scopes.push(vec![]);
+    }
+    inference.infer(expr);
+    if inference.errors.is_empty() {
+        Ok(inference.machine)
+    } else {
+        Err(inference.errors)
+    }
+}
+
+/// the type of a declaration, which is a fresh copy for every use when `generic` isn't empty
+#[derive(Debug)]
+struct Binding {
+    ty: TypeId,
+    generic: Vec<TypeId>,
+    /// set when the name is bound to a struct or an enum declaration
+    declaration: Option<Rc<Declaration>>,
+}
+
+/// the types the annotations of a struct or an enum give their fields,
+/// for checking the values made out of them where the declaration is known
+#[derive(Debug)]
+enum Declaration {
+    Struct { name: String, fields: Vec<(String, TypeId)> },
+    Enum { name: String, variants: Vec<(String, Payload)> },
+}
+
+/// unannotated fields are dynamic
+#[derive(Debug, Clone)]
+enum Payload {
+    Unit,
+    Tuple(Vec<TypeId>),
+    Struct(Vec<(String, TypeId)>),
+}
+
+#[derive(Debug)]
+struct Inference {
+    machine: Machine,
+    /// the declarations in each scope, indexed by slot like in the resolver
+    scopes: Vec<Vec<Binding>>,
+    /// the return type of each function being inferred
+    returns: Vec<TypeId>,
+    /// what `break` gives each loop being inferred
+    loops: Vec<TypeId>,
+    /// the names of every struct and enum, which annotations can use wherever they're declared
+    declared_types: HashSet<String>,
+    errors: Vec<Error>,
+}
+
+impl Inference {
+    fn fresh(&mut self) -> TypeId {
+        self.machine.insert(TypeInfo::Unknown)
+    }
+
+    fn declare(&mut self, ty: TypeId) {
+        self.scopes.last_mut().unwrap_or_else(|| ice!("there should always be a scope")).push(Binding { ty, generic: vec![], declaration: None });
+    }
+
+    /// the struct or enum `expr` names, if it's a name bound to its declaration
+    fn declaration(&self, expr: &Expression) -> Option<Rc<Declaration>> {
+        match &expr.et {
+            Expr::Identifier { local: Some(local), .. } => {
+                let scope = self.scopes.len().checked_sub(local.depth + 1)?;
+                self.scopes[scope].get(local.slot)?.declaration.clone()
+            },
+            _ => None,
+        }
+    }
+
+    /// the payload of the variant `object.name` and the enum's name, when `object` names an enum
+    fn variant_payload(&self, object: &Expression, name: &str) -> Option<(String, Payload)> {
+        match &*self.declaration(object)? {
+            Declaration::Enum { name: r#enum, variants } => variants.iter().find(|(variant, _)| variant == name).map(|(_, payload)| (r#enum.clone(), payload.clone())),
+            Declaration::Struct { .. } => None,
+        }
+    }
+
+    /// reports the fields of a literal that are missing, unknown or given more than once, and checks the others against their annotations
+    fn check_literal(&mut self, owner: &str, declared: &[(String, TypeId)], fields: &[(String, Box<Expression>)], span: Span) {
+        for (index, (field, value)) in fields.iter().enumerate() {
+            let message = if fields[..index].iter().any(|(given, _)| given == field) {
+                format!("field {} of {} is given more than once", field, owner)
+            } else {
+                match declared.iter().find(|(name, _)| name == field) {
+                    Some((_, ty)) => {
+                        self.unify(*ty, value.ty, value.span);
+                        continue
+                    },
+                    None => format!("{} has no field {}", owner, field),
+                }
+            };
+            self.errors.push(make_error!(message, codes::E0026.0, Severity::Error, None => value.span))
+        }
+        for (name, _) in declared.iter().filter(|(name, _)| !fields.iter().any(|(given, _)| given == name)) {
+            self.errors.push(make_error!(format!("missing field {} in {}", name, owner), codes::E0026.0, Severity::Error, None => span))
+        }
+    }
+
+    fn unify(&mut self, expected: TypeId, found: TypeId, span: Span) {
+        if let Err(message) = self.machine.unify(expected, found) {
+            self.report(message, span)
+        }
+    }
+
+    fn require_number(&mut self, id: TypeId, span: Span) {
+        if let Err(message) = self.machine.require_number(id) {
+            self.report(message, span)
+        }
+    }
+
+    fn report(&mut self, message: String, span: Span) {
+        self.errors.push(make_error!(
+            "mismatched types",
+            codes::E0023.0,
+            Severity::Error,
+            message.as_str() => span
+        ))
+    }
+
+    /// the function's own binding is the last one in its scope,
+    /// everything it shares with the other bindings has to stay the same for every use
+    fn generalize(&mut self, ty: TypeId) -> Vec<TypeId> {
+        let mut generic = vec![];
+        self.machine.free_variables(ty, &mut generic);
+        let mut shared = vec![];
+        let bindings = self.scopes.iter().flatten();
+        for binding in bindings.take(self.scopes.iter().map(Vec::len).sum::<usize>() - 1) {
+            let mut free = vec![];
+            self.machine.free_variables(binding.ty, &mut free);
+            shared.extend(free.into_iter().filter(|variable| !binding.generic.contains(variable)));
+        }
+        for ty in self.returns.iter().chain(self.loops.iter()) {
+            self.machine.free_variables(*ty, &mut shared);
+        }
+        generic.retain(|variable| !shared.contains(variable));
+        generic
+    }
+
+    /// structs, enums, arrays, tuples and generics are only told apart by name, unions are dynamic
+    fn annotation(&mut self, r#type: &Type) -> TypeId {
+        self.check_names(r#type);
+        let info = match &r#type.kind {
+            TypeKind::Named(name) if name == "_" => TypeInfo::Unknown,
+            TypeKind::Named(name) => match primitive(name) {
+                Some(info) => info,
+                None if self.declared_types.contains(name) => TypeInfo::Named(name.clone()),
+                // already reported as an unknown type
+                None => TypeInfo::Dynamic,
+            },
+            TypeKind::Generic(..) | TypeKind::Array(_) | TypeKind::Tuple(_) => TypeInfo::Named(stringify_type(r#type)),
+            TypeKind::Union(_) => TypeInfo::Dynamic,
+        };
+        self.machine.insert(info)
+    }
+
+    /// reports the names in `r#type` that aren't a type. there's no way to declare generics yet, so their heads can only be structs and enums
+    fn check_names(&mut self, r#type: &Type) {
+        match &r#type.kind {
+            TypeKind::Named(name) if name == "_" || primitive(name).is_some() => (),
+            TypeKind::Named(name) => self.check_declared(name, r#type.span),
+            TypeKind::Generic(name, items) => {
+                self.check_declared(name, r#type.span);
+                for item in items {
+                    self.check_names(item)
+                }
+            },
+            TypeKind::Array(item) => self.check_names(item),
+            TypeKind::Tuple(items) | TypeKind::Union(items) => for item in items {
+                self.check_names(item)
+            },
+        }
+    }
+
+    fn check_declared(&mut self, name: &str, span: Span) {
+        if !self.declared_types.contains(name) {
+            let label = format!("there's no built-in type, struct or enum called {}", name);
+            self.errors.push(make_error!(
+                "unknown type",
+                codes::E0024.0,
+                Severity::Error,
+                label.as_str() => span
+            ))
+        }
+    }
+
+    /// fields without an annotation can hold anything
+    fn field_annotation(&mut self, r#type: Option<&Type>) -> TypeId {
+        match r#type {
+            Some(r#type) => self.annotation(r#type),
+            None => self.machine.insert(TypeInfo::Dynamic),
+        }
+    }
+
+    fn annotation_or_fresh(&mut self, r#type: Option<&Type>) -> TypeId {
+        match r#type {
+            Some(r#type) => self.annotation(r#type),
+            None => self.fresh(),
+        }
+    }
+
+    fn infer_function(&mut self, arguments: Vec<TypeId>, ret: TypeId, body: &mut Expression) -> TypeId {
+        self.scopes.push(arguments.iter().map(|ty| Binding { ty: *ty, generic: vec![], declaration: None }).collect());
+        self.returns.push(ret);
+        // loops don't continue into functions
+        let loops = std::mem::take(&mut self.loops);
+        self.infer(body);
+        self.loops = loops;
+        self.returns.pop();
+        self.scopes.pop();
+        self.unify(ret, body.ty, body.span);
+        self.machine.insert(TypeInfo::Function(arguments, ret))
+    }
+
+    fn infer(&mut self, expr: &mut Expression) {
+        let span = expr.span;
+        expr.ty = match &mut expr.et {
+            Expr::Literal { src } => self.machine.insert(literal_type(src)),
+            Expr::Unary { op, right } => {
+                self.infer(right);
+                match op {
+                    UnaryOp::Not => {
+                        let bool = self.machine.insert(TypeInfo::Bool);
+                        self.unify(bool, right.ty, right.span);
+                        bool
+                    },
+                    UnaryOp::Neg => {
+                        self.require_number(right.ty, right.span);
+                        right.ty
+                    },
+                    UnaryOp::Show => right.ty,
+                    UnaryOp::Panic => self.fresh(),
+                }
+            },
+            Expr::Binary { op, left, right } => {
+                self.infer(left);
+                self.infer(right);
+                if !matches!(op, BinaryOp::Eq | BinaryOp::Neq) {
+                    self.require_number(left.ty, left.span);
+                }
+                self.unify(left.ty, right.ty, right.span);
+                match op {
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Exp => left.ty,
+                    BinaryOp::Eq | BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Leq | BinaryOp::Geq | BinaryOp::Neq => self.machine.insert(TypeInfo::Bool),
+                }
+            },
+            Expr::Semicolon { left, right } => {
+                self.infer(left);
+                self.infer(right);
+                right.ty
+            },
+            Expr::Block(Some(inside)) => {
+                self.scopes.push(vec![]);
+                self.infer(inside);
+                self.scopes.pop();
+                inside.ty
+            },
+            Expr::Block(None) | Expr::Use { .. } => self.machine.insert(TypeInfo::Nothing),
+            Expr::Module(inside, ..) => {
+                self.scopes.push(vec![]);
+                if let Some(inside) = inside {
+                    self.infer(inside);
+                }
+                self.scopes.pop();
+                let module = self.machine.insert(TypeInfo::Dynamic);
+                self.declare(module);
+                module
+            },
+            Expr::Call { callee, args } => {
+                for arg in args.iter_mut() {
+                    self.infer(arg);
+                }
+                self.infer(callee);
+                match self.machine.info(callee.ty).clone() {
+                    TypeInfo::Function(arguments, ret) if arguments.len() != args.len() => {
+                        let message = format!("expected {} argument{}, found {}", arguments.len(), if arguments.len() == 1 { "" } else { "s" }, args.len());
+                        self.report(message, span);
+                        ret
+                    },
+                    TypeInfo::Function(arguments, ret) => {
+                        for (argument, arg) in arguments.into_iter().zip(args.iter()) {
+                            self.unify(argument, arg.ty, arg.span);
+                        }
+                        ret
+                    },
+                    _ => {
+                        let ret = self.fresh();
+                        let function = self.machine.insert(TypeInfo::Function(args.iter().map(|arg| arg.ty).collect(), ret));
+                        self.unify(function, callee.ty, callee.span);
+                        ret
+                    },
+                }
+            },
+            Expr::Property { object, name } => {
+                self.infer(object);
+                match self.variant_payload(object, name) {
+                    Some((r#enum, Payload::Unit)) => self.machine.insert(TypeInfo::Named(r#enum)),
+                    // constructors of tuple variants take what the annotations say
+                    Some((r#enum, Payload::Tuple(types))) => {
+                        let variant = self.machine.insert(TypeInfo::Named(r#enum));
+                        self.machine.insert(TypeInfo::Function(types, variant))
+                    },
+                    _ => self.machine.insert(TypeInfo::Dynamic),
+                }
+            },
+            Expr::Cast { value, to } => {
+                self.infer(value);
+                self.require_number(value.ty, value.span);
+                let ty = self.annotation(to);
+                if !matches!(self.machine.info(ty), TypeInfo::Integer(Some(_)) | TypeInfo::Float(Some(_))) {
+                    self.report(format!("expected a number type, found {}", stringify_type(to)), to.span);
+                }
+                ty
+            },
+            Expr::Construct { of, fields } => {
+                for (_, value) in fields.iter_mut() {
+                    self.infer(value);
+                }
+                self.infer(of);
+                // (what the literal makes, how it's called in errors, its fields)
+                let declared = match &of.et {
+                    Expr::Property { object, name } => match self.variant_payload(object, name) {
+                        Some((r#enum, Payload::Struct(declared))) => Some((r#enum.clone(), format!("{}.{}", r#enum, name), declared)),
+                        _ => None,
+                    },
+                    _ => match self.declaration(of).as_deref() {
+                        Some(Declaration::Struct { name, fields }) => Some((name.clone(), name.clone(), fields.clone())),
+                        _ => None,
+                    },
+                };
+                match declared {
+                    Some((r#type, owner, declared)) => {
+                        self.check_literal(&owner, &declared, fields, span);
+                        self.machine.insert(TypeInfo::Named(r#type))
+                    },
+                    None => self.machine.insert(TypeInfo::Dynamic),
+                }
+            },
+            Expr::Break { with } => {
+                let value = match with {
+                    Some(value) => {
+                        self.infer(value);
+                        value.ty
+                    },
+                    None => self.machine.insert(TypeInfo::Nothing),
+                };
+                // the compiler reports a `break` outside of a loop
+                if let Some(target) = self.loops.last().copied() {
+                    self.unify(target, value, with.as_ref().map_or(span, |value| value.span));
+                }
+                self.fresh()
+            },
+            Expr::Continue => self.fresh(),
+            Expr::Return { with } => {
+                let value = match with {
+                    Some(value) => {
+                        self.infer(value);
+                        value.ty
+                    },
+                    None => self.machine.insert(TypeInfo::Nothing),
+                };
+                if let Some(target) = self.returns.last().copied() {
+                    self.unify(target, value, with.as_ref().map_or(span, |value| value.span));
+                }
+                self.fresh()
+            },
+            Expr::Loop { inside } => {
+                let ty = self.fresh();
+                self.loops.push(ty);
+                self.infer(inside);
+                self.loops.pop();
+                ty
+            },
+            Expr::If { condition, then, else_ } => {
+                self.infer(condition);
+                let bool = self.machine.insert(TypeInfo::Bool);
+                self.unify(bool, condition.ty, condition.span);
+                self.infer(then);
+                match else_ {
+                    Some(else_) => {
+                        self.infer(else_);
+                        self.unify(then.ty, else_.ty, else_.span);
+                        then.ty
+                    },
+                    None => self.machine.insert(TypeInfo::Nothing),
+                }
+            },
+            Expr::Assign { left, right } => {
+                self.infer(left);
+                self.infer(right);
+                self.unify(left.ty, right.ty, right.span);
+                self.machine.insert(TypeInfo::Nothing)
+            },
+            Expr::AssignOp { left, right, .. } => {
+                self.infer(left);
+                self.infer(right);
+                self.require_number(left.ty, left.span);
+                self.unify(left.ty, right.ty, right.span);
+                self.machine.insert(TypeInfo::Nothing)
+            },
+            Expr::Identifier { local, .. } => match local {
+                Some(local) => {
+                    let scope = self.scopes.len().checked_sub(local.depth + 1).unwrap_or_else(|| ice!("the resolver bound a name to a scope that doesn't exist"));
+                    let binding = self.scopes[scope].get(local.slot).unwrap_or_else(|| ice!("the resolver bound a name to a slot that doesn't exist"));
+                    let (ty, generic) = (binding.ty, binding.generic.clone());
+                    self.machine.instantiate(ty, &generic)
+                },
+                // globals, and names only a closure's captured scopes might have
+                None => self.machine.insert(TypeInfo::Dynamic),
+            },
+            Expr::Placeholder => self.fresh(),
+            Expr::Fn { body, annotations, ret, .. } => {
+                // declared first so that the function can call itself, with the same types
+                let ty = self.fresh();
+                self.declare(ty);
+                let arguments = annotations.iter().map(|annotation| self.annotation_or_fresh(annotation.as_ref())).collect();
+                let ret = self.annotation_or_fresh(ret.as_ref());
+                let function = self.infer_function(arguments, ret, body);
+                self.unify(ty, function, span);
+                let generic = self.generalize(ty);
+                let binding = self.scopes.last_mut().and_then(|scope| scope.last_mut()).unwrap_or_else(|| ice!("the function was just declared"));
+                binding.generic = generic;
+                ty
+            },
+            Expr::Lambda { args, body } => {
+                let arguments = args.iter().map(|_| self.fresh()).collect();
+                let ret = self.fresh();
+                self.infer_function(arguments, ret, body)
+            },
+            Expr::Let { value, annotation, .. } => {
+                let ty = self.annotation_or_fresh(annotation.as_ref());
+                if let Some(value) = value {
+                    self.infer(value);
+                    self.unify(ty, value.ty, value.span);
+                }
+                self.declare(ty);
+                self.machine.insert(TypeInfo::Nothing)
+            },
+            Expr::While { condition, body } => {
+                self.infer(condition);
+                let bool = self.machine.insert(TypeInfo::Bool);
+                self.unify(bool, condition.ty, condition.span);
+                let nothing = self.machine.insert(TypeInfo::Nothing);
+                self.loops.push(nothing);
+                self.infer(body);
+                self.loops.pop();
+                nothing
+            },
+            Expr::Match { value, arms } => {
+                self.infer(value);
+                let ty = self.fresh();
+                for arm in arms {
+                    self.infer_pattern_paths(&mut arm.pattern, value.ty);
+                    self.scopes.push(vec![]);
+                    self.declare_pattern_bindings(&mut arm.pattern, value.ty);
+                    self.infer(&mut arm.body);
+                    self.scopes.pop();
+                    self.unify(ty, arm.body.ty, arm.body.span);
+                }
+                ty
+            },
+            Expr::EnumDecl { name, variants, .. } => {
+                let variants = variants.iter().map(|variant| {
+                    let payload = match &variant.payload {
+                        EnumPayload::Unit => Payload::Unit,
+                        EnumPayload::Tuple(types) => Payload::Tuple(types.iter().map(|r#type| self.annotation(r#type)).collect()),
+                        EnumPayload::Struct(fields) => Payload::Struct(fields.iter().map(|(name, r#type)| (name.clone(), self.field_annotation(r#type.as_ref()))).collect()),
+                    };
+                    (variant.name.clone(), payload)
+                }).collect();
+                let ty = self.machine.insert(TypeInfo::Dynamic);
+                self.declare(ty);
+                let binding = self.scopes.last_mut().and_then(|scope| scope.last_mut()).unwrap_or_else(|| ice!("the enum was just declared"));
+                binding.declaration = Some(Rc::new(Declaration::Enum { name: name.clone(), variants }));
+                ty
+            },
+            Expr::StructDecl { name, fields, .. } => {
+                let fields = fields.iter().map(|(name, r#type)| (name.clone(), self.field_annotation(r#type.as_ref()))).collect();
+                let ty = self.machine.insert(TypeInfo::Dynamic);
+                self.declare(ty);
+                let binding = self.scopes.last_mut().and_then(|scope| scope.last_mut()).unwrap_or_else(|| ice!("the struct was just declared"));
+                binding.declaration = Some(Rc::new(Declaration::Struct { name: name.clone(), fields }));
+                ty
+            },
+        }
+    }
+
+    /// also checks literal patterns against the type of the value being matched
+    fn infer_pattern_paths(&mut self, pattern: &mut Pattern, value: TypeId) {
+        match &mut pattern.pt {
+            Pat::Literal { src, negative, ty } => {
+                *ty = self.machine.insert(literal_type(src));
+                self.unify(value, *ty, pattern.span);
+                if *negative {
+                    self.require_number(*ty, pattern.span);
+                }
+            },
+            Pat::Variant { path, payload } => {
+                self.infer(path);
+                let dynamic = self.machine.insert(TypeInfo::Dynamic);
+                for item in payload_items(payload) {
+                    self.infer_pattern_paths(item, dynamic)
+                }
+            },
+            Pat::Wildcard | Pat::Binding { .. } => (),
+        }
+    }
+
+    /// in the same order as the resolver declares them
+    fn declare_pattern_bindings(&mut self, pattern: &mut Pattern, value: TypeId) {
+        match &mut pattern.pt {
+            Pat::Binding { .. } => self.declare(value),
+            Pat::Variant { payload, .. } => {
+                let dynamic = self.machine.insert(TypeInfo::Dynamic);
+                for item in payload_items(payload) {
+                    self.declare_pattern_bindings(item, dynamic)
+                }
+            },
+            Pat::Literal { .. } | Pat::Wildcard => (),
+        }
+    }
+}
+
+fn collect_declared_types(expr: &mut Expression, declared: &mut HashSet<String>) {
+    if let Expr::StructDecl { name, .. } | Expr::EnumDecl { name, .. } = &expr.et {
+        declared.insert(name.clone());
+    }
+    for child in ast::children_mut(expr) {
+        collect_declared_types(child, declared)
+    }
+}
+
+/// the built-in types, by the name annotations use for them
+fn primitive(name: &str) -> Option<TypeInfo> {
+    Some(match name {
+        "u8"  => TypeInfo::Integer(Some(Integer::U8)),
+        "u16" => TypeInfo::Integer(Some(Integer::U16)),
+        "u32" => TypeInfo::Integer(Some(Integer::U32)),
+        "u64" => TypeInfo::Integer(Some(Integer::U64)),
+        "u128" => TypeInfo::Integer(Some(Integer::U128)),
+        "i8"  => TypeInfo::Integer(Some(Integer::I8)),
+        "i16" => TypeInfo::Integer(Some(Integer::I16)),
+        "i32" => TypeInfo::Integer(Some(Integer::I32)),
+        "i64" => TypeInfo::Integer(Some(Integer::I64)),
+        "i128" => TypeInfo::Integer(Some(Integer::I128)),
+        "f32" => TypeInfo::Float(Some(Float::F32)),
+        "f64" => TypeInfo::Float(Some(Float::F64)),
+        "char" => TypeInfo::Char,
+        "bool" => TypeInfo::Bool,
+        "str" => TypeInfo::Str,
+        "nothing" => TypeInfo::Nothing,
+        _ => return None,
+    })
+}
+
+fn literal_type(src: &TokenType) -> TypeInfo {
+    match src {
+        TokenType::ILiteral { type_hint, .. } => match type_hint {
+            ILiteralTypeHint::None => TypeInfo::Number,
+            ILiteralTypeHint::U8 => TypeInfo::Integer(Some(Integer::U8)),
+            ILiteralTypeHint::U16 => TypeInfo::Integer(Some(Integer::U16)),
+            ILiteralTypeHint::U32 => TypeInfo::Integer(Some(Integer::U32)),
+            ILiteralTypeHint::U64 => TypeInfo::Integer(Some(Integer::U64)),
+            ILiteralTypeHint::U128 => TypeInfo::Integer(Some(Integer::U128)),
+            ILiteralTypeHint::I8 => TypeInfo::Integer(Some(Integer::I8)),
+            ILiteralTypeHint::I16 => TypeInfo::Integer(Some(Integer::I16)),
+            ILiteralTypeHint::I32 => TypeInfo::Integer(Some(Integer::I32)),
+            ILiteralTypeHint::I64 => TypeInfo::Integer(Some(Integer::I64)),
+            ILiteralTypeHint::I128 => TypeInfo::Integer(Some(Integer::I128)),
+        },
+        TokenType::FLiteral { type_hint, .. } => match type_hint {
+            FLiteralTypeHint::None => TypeInfo::Float(None),
+            FLiteralTypeHint::F32 => TypeInfo::Float(Some(Float::F32)),
+            FLiteralTypeHint::F64 => TypeInfo::Float(Some(Float::F64)),
+        },
+        TokenType::SLiteral { .. } => TypeInfo::Str,
+        TokenType::BLiteral(_) => TypeInfo::Bool,
+        _ => ice!("literal is not a literal"),
+    }
+}
+
+fn payload_items(payload: &mut PatPayload) -> Vec<&mut Pattern> {
+    match payload {
+        PatPayload::Unit => vec![],
+        PatPayload::Tuple(items) => items.iter_mut().collect(),
+        PatPayload::Struct(fields) => fields.iter_mut().map(|(_, item)| item).collect(),
+    }
+}
@@ -204,6 +204,7 @@ pub fn lex(input: &crate::loader::Source) -> (Vec<Token>, Result<(), Vec<Error>>
             '.' => simple_token!(Dot),
             ':' => simple_token!(Colon),
             ',' => simple_token!(Comma),
+            '|' => simple_token!(Pipe),
             '→' => simple_token!(Ret),
             '-' if matches!(peek!(), Some('>')) => two_char_token!(Ret),
             '-' if matches!(peek!(), Some('=')) => two_char_token!(MinusEq),
@@ -211,6 +212,7 @@ pub fn lex(input: &crate::loader::Source) -> (Vec<Token>, Result<(), Vec<Error>>
             '!' if matches!(peek!(), Some('=')) => two_char_token!(Neq),
             '/' if matches!(peek!(), Some('=')) => two_char_token!(SlashEq),
             '=' if matches!(peek!(), Some('=')) => two_char_token!(EqEq),
+            '=' if matches!(peek!(), Some('>')) => two_char_token!(FatArrow),
             '<' if matches!(peek!(), Some('=')) => two_char_token!(Leq),
             '>' if matches!(peek!(), Some('=')) => two_char_token!(Geq),
             '-' => simple_token!(Minus),
@@ -255,7 +257,7 @@ pub fn lex(input: &crate::loader::Source) -> (Vec<Token>, Result<(), Vec<Error>>
                     error_accumulator.push(make_error!("empty hexadecimal literal", codes::E0003.0, Error, None => Span::new(file, n, idx + 1)));
                     value.push('0');
                 };
-                let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64);
+                let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64, "u128": ILiteralTypeHint::U128, "i128": ILiteralTypeHint::I128);
                 Some(Token { span: Span::new(file, n, idx + 1), tt: ILiteral { value, kind: IntLiteralType::Hexadecimal, type_hint } })
             },
             '0' if matches!(peek!(), Some('o')) => {
@@ -271,7 +273,7 @@ pub fn lex(input: &crate::loader::Source) -> (Vec<Token>, Result<(), Vec<Error>>
                     error_accumulator.push(make_error!("empty octal literal", codes::E0003.0, Error, None => Span::new(file, n, idx + 1)));
                     value.push('0');
                 };
-                let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64);
+                let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64, "u128": ILiteralTypeHint::U128, "i128": ILiteralTypeHint::I128);
                 Some(Token { span: Span::new(file, n, idx + 1), tt: ILiteral { value, kind: IntLiteralType::Octal, type_hint } })
             },
             '0' if matches!(peek!(), Some('b')) => {
@@ -287,7 +289,7 @@ pub fn lex(input: &crate::loader::Source) -> (Vec<Token>, Result<(), Vec<Error>>
                     error_accumulator.push(make_error!("empty binary literal", codes::E0003.0, Error, None => Span::new(file, n, idx + 1)));
                     value.push('0');
                 };
-                let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64);
+                let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64, "u128": ILiteralTypeHint::U128, "i128": ILiteralTypeHint::I128);
                 Some(Token { span: Span::new(file, n, idx + 1), tt: ILiteral { value, kind: IntLiteralType::Binary, type_hint } })
             },
             x @ '0'..='9' => {
@@ -315,7 +317,7 @@ pub fn lex(input: &crate::loader::Source) -> (Vec<Token>, Result<(), Vec<Error>>
                         }
                     } else {
                         idx -= 1;
-                        let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64);
+                        let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64, "u128": ILiteralTypeHint::U128, "i128": ILiteralTypeHint::I128);
 
                         Token {
                             span: Span::new(file, n, idx + 1),
@@ -323,7 +325,7 @@ pub fn lex(input: &crate::loader::Source) -> (Vec<Token>, Result<(), Vec<Error>>
                         }
                     }
                 } else {
-                    let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64);
+                    let (type_hint, _is_invalid) = rth!(["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128"], "integer", ILiteralTypeHint::None, "u8": ILiteralTypeHint::U8, "i8": ILiteralTypeHint::I8, "u16": ILiteralTypeHint::U16, "i16": ILiteralTypeHint::I16, "u32": ILiteralTypeHint::U32, "i32": ILiteralTypeHint::I32, "u64": ILiteralTypeHint::U64, "i64": ILiteralTypeHint::I64, "u128": ILiteralTypeHint::U128, "i128": ILiteralTypeHint::I128);
                     Token {
                         span: Span::new(file, n, idx + 1),
                         tt: ILiteral { value, kind: IntLiteralType::Decimal, type_hint }
@@ -2,16 +2,25 @@
 
 pub mod errors;
 pub mod ast;
+pub mod coverage;
+pub mod dap;
+pub mod debugger;
+pub mod engine;
+pub mod inference;
 pub(crate) mod ice; // must be first due to how macros work
 pub mod lexer;
 pub mod loader;
 pub mod parser;
+pub mod profiler;
+pub mod resolver;
 pub mod token_debugger;
 pub mod tokens;
 pub mod vm;
 pub mod compiler;
 pub mod typing;
 
+pub use engine::Engine;
+
 pub mod failure {
     use std::{io::Error, string::FromUtf8Error};
 
@@ -16,7 +16,7 @@ macro_rules! ok {
     }};
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Span {
     pub file: usize,
     pub start: u32,
@@ -69,6 +69,17 @@ impl Source {
             FileSource::PathBuf(ref s) => s.as_os_str().to_str()
         }
     }
+
+    /// where each line starts, counted in chars like spans are
+    pub fn line_starts(&self) -> Vec<u32> {
+        let newlines = self.string.chars().enumerate().filter(|(_, c)| *c == '\n').map(|(index, _)| index as u32 + 1);
+        std::iter::once(0).chain(newlines).collect()
+    }
+}
+
+/// the line (starting at 0) `position` is on, given what `Source::line_starts` returned
+pub fn line_of(line_starts: &[u32], position: u32) -> usize {
+    line_starts.partition_point(|start| *start <= position) - 1
 }
 
 #[derive(Debug, Default)]
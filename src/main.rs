@@ -4,6 +4,49 @@ use std::path::PathBuf;
 // so no patterns for now.
 
 fn main() {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("debug") => {
+            let Some(path) = args.next() else {
+                eprintln!("usage: eeeee debug <file>");
+                return
+            };
+            if let Err(failure) = eeeee::debugger::debug_file(&path) {
+                eprintln!("couldn't read {}: {:?}", path, failure);
+            }
+            return
+        },
+        Some("profile") => {
+            let Some(path) = args.next() else {
+                eprintln!("usage: eeeee profile <file> [folded stacks output]");
+                return
+            };
+            let folded = args.next().map(PathBuf::from);
+            if let Err(failure) = eeeee::profiler::profile_file(&path, folded.as_deref()) {
+                eprintln!("couldn't read {} or write the folded stacks: {:?}", path, failure);
+            }
+            return
+        },
+        Some("coverage") => {
+            let Some(path) = args.next() else {
+                eprintln!("usage: eeeee coverage <file> [lcov output]");
+                return
+            };
+            let lcov = args.next().map(PathBuf::from);
+            if let Err(failure) = eeeee::coverage::coverage_file(&path, lcov.as_deref()) {
+                eprintln!("couldn't read {} or write the lcov tracefile: {:?}", path, failure);
+            }
+            return
+        },
+        Some("dap") => {
+            if let Err(error) = eeeee::dap::serve(std::io::stdin().lock(), std::io::stdout().lock()) {
+                eprintln!("debug adapter stopped: {}", error);
+            }
+            return
+        },
+        _ => (),
+    }
+
     let mut loader = eeeee::loader::Loader::new();
     let index;
     #[cfg(miri)]
@@ -36,17 +79,42 @@ fn main() {
                     eeeee::errors::print_error(error, &loader);
                 }
             }
-            Ok(expr) => {
+            Ok(mut expr) => {
+                if let Err(errors) = eeeee::resolver::resolve(&mut expr) {
+                    for error in errors.iter() {
+                        eeeee::errors::print_error(error, &loader);
+                    }
+                    return
+                }
+                let types = match eeeee::inference::infer(&mut expr) {
+                    Ok(types) => types,
+                    Err(errors) => {
+                        for error in errors.iter() {
+                            eeeee::errors::print_error(error, &loader);
+                        }
+                        return
+                    },
+                };
                 eeeee::ast::show_tree(&expr);
                 eprintln!("PARSED");
-                let mut builder = eeeee::compiler::ProgramBuilder::new(true);
+                let mut builder = eeeee::compiler::ProgramBuilder::new(true, types);
                 eeeee::compiler::lower(&expr, &mut builder);
-                eprintln!("COMPILED");
-                let program = builder.finish();
-                eeeee::vm::show_program(&program);
-                let mut vm = eeeee::vm::VM::new(program);
-                vm.run();
-                eprintln!("EXECUTED");
+                match builder.finish() {
+                    Err(errors) => {
+                        for error in errors.iter() {
+                            eeeee::errors::print_error(error, &loader);
+                        }
+                    }
+                    Ok(program) => {
+                        eprintln!("COMPILED");
+                        eeeee::vm::show_program(&program);
+                        let mut vm = eeeee::vm::VM::new(program);
+                        match vm.run() {
+                            Err(error) => eeeee::errors::print_error(&error.to_error(), &loader),
+                            Ok(_) => eprintln!("EXECUTED"),
+                        }
+                    }
+                }
                 // eprintln!("parsed: {:?}", expr)
             },
         }
@@ -3,6 +3,7 @@ mod precedence;
 use crate::{
     errors::{codes, make_error, Error, Severity},
     ast::{self, Expression},
+    typing::TypeId,
     ice::ice,
     loader::{Span, Loader},
     tokens::{Token, TokenType},
@@ -13,8 +14,273 @@ pub fn parse(input: &[Token], file: usize, loader: &mut Loader) -> Result<Box<Ex
     let mut pointer = 0usize;
     match parse_impl(input, 0, &mut pointer, &mut errors, file, loader) {
         Err(_) => Err(errors),
-        Ok(val) => Ok(val),
+        Ok(mut val) => {
+            let mut leftovers = vec![];
+            find_placeholders(&mut val, &mut leftovers, true);
+            for placeholder in leftovers {
+                errors.push(make_error!(
+                    "'_' can only be used inside of a function argument",
+                    codes::E0017.0,
+                    Severity::Error,
+                    "use an explicit lambda (|x| ...) instead" => placeholder.span
+                ));
+            }
+            if errors.is_empty() {
+                Ok(val)
+            } else {
+                Err(errors)
+            }
+        },
+    }
+}
+
+fn find_placeholders<'a>(expr: &'a mut Expression, found: &mut Vec<&'a mut Expression>, enter_functions: bool) {
+    if matches!(expr.et, ast::Expr::Placeholder) {
+        found.push(expr);
+        return
+    }
+    if !enter_functions && matches!(expr.et, ast::Expr::Fn { .. } | ast::Expr::Lambda { .. }) {
+        return
+    }
+    for child in ast::children_mut(expr) {
+        find_placeholders(child, found, enter_functions);
+    }
+}
+
+fn parse_pattern(input: &[Token], pointer: &mut usize, accumulator: &mut Vec<Error>, file: usize) -> Result<ast::Pattern, ()> {
+    let token = if let Some(token) = input.get(*pointer) {
+        *pointer += 1;
+        token
+    } else {
+        accumulator.push(make_error!(
+            "expected a pattern, got EOF",
+            codes::E0018.0,
+            Severity::FatalError,
+            None => Span { file, start: input[*pointer - 1].span.end, end: input[*pointer - 1].span.end }
+        ));
+        return Err(())
+    };
+    let (pt, span) = match &token.tt {
+        tt @ (TokenType::ILiteral { .. } | TokenType::FLiteral { .. } | TokenType::SLiteral { .. } | TokenType::BLiteral(_)) => {
+            (ast::Pat::Literal { src: tt.clone(), negative: false, ty: TypeId::default() }, token.span)
+        },
+        TokenType::Minus => {
+            match input.get(*pointer) {
+                Some(Token { tt: tt @ (TokenType::ILiteral { .. } | TokenType::FLiteral { .. }), span }) => {
+                    *pointer += 1;
+                    (ast::Pat::Literal { src: tt.clone(), negative: true, ty: TypeId::default() }, token.span.merge(*span))
+                },
+                _ => {
+                    accumulator.push(make_error!(
+                        "expected a number after '-' in pattern",
+                        codes::E0018.0,
+                        Severity::FatalError,
+                        None => token.span
+                    ));
+                    return Err(())
+                }
+            }
+        },
+        TokenType::Ident(name) if name == "_" => (ast::Pat::Wildcard, token.span),
+        TokenType::Ident(name) if matches!(input.get(*pointer), Some(Token { tt: TokenType::Dot | TokenType::LParen | TokenType::LCBrace, .. })) => {
+            let mut path = Box::new(Expression { et: ast::Expr::Identifier { id: name.clone(), local: None }, span: token.span, ty: TypeId::default() });
+            while let Some(Token { tt: TokenType::Dot, .. }) = input.get(*pointer) {
+                *pointer += 1;
+                let segment = expect_token(input, pointer, accumulator, file, "a variant name after '.'", |tt| matches!(tt, TokenType::Ident(_)))?;
+                if let TokenType::Ident(ref name) = segment.tt {
+                    let span = path.span.merge(segment.span);
+                    path = Box::new(Expression { et: ast::Expr::Property { object: path, name: name.clone() }, span, ty: TypeId::default() });
+                }
+            }
+            let payload = match input.get(*pointer) {
+                Some(Token { tt: TokenType::LParen, .. }) => {
+                    *pointer += 1;
+                    let mut items = vec![];
+                    while !matches!(input.get(*pointer), Some(Token { tt: TokenType::RParen, .. })) {
+                        items.push(parse_pattern(input, pointer, accumulator, file)?);
+                        if let Some(Token { tt: TokenType::Comma, .. }) = input.get(*pointer) {
+                            *pointer += 1;
+                        } else {
+                            break
+                        }
+                    }
+                    expect_token(input, pointer, accumulator, file, "')' after variant pattern", |tt| matches!(tt, TokenType::RParen))?;
+                    ast::PatPayload::Tuple(items)
+                },
+                Some(Token { tt: TokenType::LCBrace, .. }) => {
+                    *pointer += 1;
+                    let mut fields = vec![];
+                    while !matches!(input.get(*pointer), Some(Token { tt: TokenType::RCBrace, .. })) {
+                        let field = expect_token(input, pointer, accumulator, file, "a field name", |tt| matches!(tt, TokenType::Ident(_)))?;
+                        let field_name = if let TokenType::Ident(ref name) = field.tt { name.clone() } else { ice!("expect_token let something else through") };
+                        // `{ x }` is short for `{ x: x }`
+                        let pattern = if let Some(Token { tt: TokenType::Colon, .. }) = input.get(*pointer) {
+                            *pointer += 1;
+                            parse_pattern(input, pointer, accumulator, file)?
+                        } else {
+                            ast::Pattern { pt: ast::Pat::Binding { name: field_name.clone(), slot: 0 }, span: field.span }
+                        };
+                        fields.push((field_name, pattern));
+                        if let Some(Token { tt: TokenType::Comma, .. }) = input.get(*pointer) {
+                            *pointer += 1;
+                        } else {
+                            break
+                        }
+                    }
+                    expect_token(input, pointer, accumulator, file, "'}' after variant pattern", |tt| matches!(tt, TokenType::RCBrace))?;
+                    ast::PatPayload::Struct(fields)
+                },
+                _ => ast::PatPayload::Unit,
+            };
+            (ast::Pat::Variant { path, payload }, token.span.merge(input[*pointer - 1].span))
+        },
+        TokenType::Ident(name) => (ast::Pat::Binding { name: name.clone(), slot: 0 }, token.span),
+        tt => {
+            accumulator.push(make_error!(
+                format!("expected a pattern, got {}", tt.name_for_errors()),
+                codes::E0018.0,
+                Severity::FatalError,
+                None => token.span
+            ));
+            return Err(())
+        }
+    };
+    Ok(ast::Pattern { pt, span })
+}
+
+/// `T1 | T2 | ...`, where each one is a name, `Name<T1, T2>`, `[T]` or `(T1, T2,)`
+fn parse_type(input: &[Token], pointer: &mut usize, accumulator: &mut Vec<Error>, file: usize) -> Result<ast::Type, ()> {
+    let mut variants = vec![parse_single_type(input, pointer, accumulator, file)?];
+    while let Some(Token { tt: TokenType::Pipe, .. }) = input.get(*pointer) {
+        *pointer += 1;
+        variants.push(parse_single_type(input, pointer, accumulator, file)?);
+    }
+    if variants.len() == 1 {
+        return Ok(variants.pop().unwrap_or_else(|| ice!("there is exactly one variant")))
+    }
+    let span = variants[0].span.merge(variants[variants.len() - 1].span);
+    Ok(ast::Type { kind: ast::TypeKind::Union(variants), span })
+}
+
+fn parse_single_type(input: &[Token], pointer: &mut usize, accumulator: &mut Vec<Error>, file: usize) -> Result<ast::Type, ()> {
+    let token = expect_token(input, pointer, accumulator, file, "a type", |tt| matches!(tt, TokenType::Ident(_) | TokenType::LBracket | TokenType::LParen))?;
+    match &token.tt {
+        TokenType::Ident(name) if matches!(input.get(*pointer), Some(Token { tt: TokenType::Lt, .. })) => {
+            *pointer += 1;
+            let (arguments, _, end) = parse_type_list(input, pointer, accumulator, file, "'>' after generic arguments", |tt| matches!(tt, TokenType::Gt))?;
+            Ok(ast::Type { kind: ast::TypeKind::Generic(name.clone(), arguments), span: token.span.merge(end) })
+        },
+        TokenType::Ident(name) => Ok(ast::Type { kind: ast::TypeKind::Named(name.clone()), span: token.span }),
+        TokenType::LBracket => {
+            let item = parse_type(input, pointer, accumulator, file)?;
+            let end = expect_token(input, pointer, accumulator, file, "']' after array item type", |tt| matches!(tt, TokenType::RBracket))?;
+            Ok(ast::Type { kind: ast::TypeKind::Array(Box::new(item)), span: token.span.merge(end.span) })
+        },
+        TokenType::LParen => {
+            let (mut items, trailing_comma, end) = parse_type_list(input, pointer, accumulator, file, "')' after tuple item types", |tt| matches!(tt, TokenType::RParen))?;
+            // `(T)` is only `T` in parentheses
+            if items.len() == 1 && !trailing_comma {
+                let mut item = items.pop().unwrap_or_else(|| ice!("there is exactly one item"));
+                item.span = token.span.merge(end);
+                return Ok(item)
+            }
+            Ok(ast::Type { kind: ast::TypeKind::Tuple(items), span: token.span.merge(end) })
+        },
+        _ => ice!("expect_token let something else through"),
+    }
+}
+
+/// comma separated types up to the token `closing` accepts, which is consumed.
+/// also returns whether there was a trailing comma, and the span of the closing token
+fn parse_type_list(input: &[Token], pointer: &mut usize, accumulator: &mut Vec<Error>, file: usize, expected: &str, closing: impl Fn(&TokenType) -> bool) -> Result<(Vec<ast::Type>, bool, Span), ()> {
+    let mut items = vec![];
+    let mut trailing_comma = false;
+    while !input.get(*pointer).is_some_and(|token| closing(&token.tt)) {
+        items.push(parse_type(input, pointer, accumulator, file)?);
+        trailing_comma = matches!(input.get(*pointer), Some(Token { tt: TokenType::Comma, .. }));
+        if trailing_comma {
+            *pointer += 1;
+        } else {
+            break
+        }
+    }
+    let end = expect_token(input, pointer, accumulator, file, expected, closing)?;
+    Ok((items, trailing_comma, end.span))
+}
+
+/// `name: T, name, ...` up to and including the closing '}', where the types are optional.
+/// `what` is what the fields belong to in the error for the missing '}'
+fn parse_field_declarations(input: &[Token], pointer: &mut usize, accumulator: &mut Vec<Error>, file: usize, what: &str) -> Result<Vec<(String, Option<ast::Type>)>, ()> {
+    let mut fields: Vec<(String, Option<ast::Type>)> = vec![];
+    while !matches!(input.get(*pointer), Some(Token { tt: TokenType::RCBrace, .. })) {
+        let field = expect_token(input, pointer, accumulator, file, "a field name", |tt| matches!(tt, TokenType::Ident(_)))?;
+        let field_name = if let TokenType::Ident(ref name) = field.tt { name.clone() } else { ice!("expect_token let something else through") };
+        if fields.iter().any(|(name, _)| name == &field_name) {
+            accumulator.push(make_error!(
+                format!("field {} is declared more than once", field_name),
+                codes::E0019.0,
+                Severity::Error,
+                None => field.span
+            ));
+        }
+        let r#type = if let Some(Token { tt: TokenType::Colon, .. }) = input.get(*pointer) {
+            *pointer += 1;
+            Some(parse_type(input, pointer, accumulator, file)?)
+        } else {
+            None
+        };
+        fields.push((field_name, r#type));
+        if let Some(Token { tt: TokenType::Comma, .. }) = input.get(*pointer) {
+            *pointer += 1;
+        } else {
+            break
+        }
     }
+    expect_token(input, pointer, accumulator, file, &format!("'}}' after {} fields", what), |tt| matches!(tt, TokenType::RCBrace))?;
+    Ok(fields)
+}
+
+/// consumes the next token if it satisfies `predicate`, and reports a fatal error otherwise
+fn expect_token<'a>(input: &'a [Token], pointer: &mut usize, accumulator: &mut Vec<Error>, file: usize, expected: &str, predicate: impl Fn(&TokenType) -> bool) -> Result<&'a Token, ()> {
+    match input.get(*pointer) {
+        Some(token) if predicate(&token.tt) => {
+            *pointer += 1;
+            Ok(token)
+        },
+        Some(token) => {
+            accumulator.push(make_error!(
+                format!("expected {}, got {}", expected, token.tt.name_for_errors()),
+                codes::E0012.0,
+                Severity::FatalError,
+                None => token.span
+            ));
+            Err(())
+        },
+        None => {
+            accumulator.push(make_error!(
+                format!("expected {}, got EOF", expected),
+                codes::E0012.0,
+                Severity::FatalError,
+                None => Span { file, start: input[*pointer - 1].span.end, end: input[*pointer - 1].span.end }
+            ));
+            Err(())
+        },
+    }
+}
+
+/// turns `expr` into `|_| expr`, where the placeholders it contains refer to the lambda's argument
+fn placeholder_lambda(mut expr: Box<Expression>) -> Box<Expression> {
+    let mut found = vec![];
+    find_placeholders(&mut expr, &mut found, false);
+    for placeholder in found {
+        placeholder.et = ast::Expr::Identifier { id: "_".to_owned(), local: None };
+    }
+    let span = expr.span;
+    Box::new(Expression {
+        et: ast::Expr::Lambda { body: expr, args: vec!["_".to_owned()] },
+        span,
+        ty: TypeId::default(),
+    })
 }
 
 fn parse_impl(
@@ -114,6 +380,12 @@ fn parse_impl(
               | TokenType::FLiteral { .. }
               | TokenType::BLiteral { .. }
               | TokenType::Break
+              | TokenType::Continue
+              | TokenType::Return
+              | TokenType::Match
+              | TokenType::Enum
+              | TokenType::Struct
+              | TokenType::Pipe
               | TokenType::LParen
               | TokenType::LCBrace
               | TokenType::Not
@@ -133,34 +405,150 @@ fn parse_impl(
         }
     }
 
+    macro_rules! match_arms {
+        () => {{
+            expect!(TokenType::LCBrace, true, " after match value");
+            let mut arms: Vec<ast::MatchArm> = vec![];
+            loop {
+                if let Some(Token { tt: TokenType::RCBrace, .. }) = peek!() {
+                    break
+                }
+                let pattern = parse_pattern(input, pointer, accumulator, file)?;
+                expect!(TokenType::FatArrow, true, " after pattern");
+                let body = parse_impl(input, 0, pointer, accumulator, file, loader)?;
+                arms.push(ast::MatchArm { pattern, body });
+                if let Some(Token { tt: TokenType::Comma, .. }) = peek!() {
+                    next!();
+                } else {
+                    break
+                }
+            }
+            expect!(TokenType::RCBrace, true, " after match arms");
+            arms
+        }};
+    }
+
     let mut lhs: Box<Expression>;
+
+    // `Path { field: ...` is a struct literal, while `Path { ...` (without the colon) stays a block
+    macro_rules! struct_literal {
+        () => {
+            if matches!(
+                (input.get(*pointer), input.get(*pointer + 1), input.get(*pointer + 2)),
+                (Some(Token { tt: TokenType::LCBrace, .. }), Some(Token { tt: TokenType::Ident(_), .. }), Some(Token { tt: TokenType::Colon, .. }))
+            ) {
+                next!();
+                let mut fields: Vec<(String, Box<Expression>)> = vec![];
+                loop {
+                    if let Some(Token { tt: TokenType::RCBrace, .. }) = peek!() {
+                        break
+                    }
+                    let field = expect_token(input, pointer, accumulator, file, "a field name", |tt| matches!(tt, TokenType::Ident(_)))?;
+                    let field_name = if let TokenType::Ident(ref name) = field.tt { name.clone() } else { ice!("expect_token let something else through") };
+                    expect!(TokenType::Colon, true, " after field name");
+                    fields.push((field_name, parse_impl(input, 0, pointer, accumulator, file, loader)?));
+                    if let Some(Token { tt: TokenType::Comma, .. }) = peek!() {
+                        next!();
+                    } else {
+                        break
+                    }
+                }
+                expect!(TokenType::RCBrace, true, " after struct literal fields");
+                let lhs_span = lhs.span;
+                lhs = Box::new(Expression {
+                    et: ast::Expr::Construct { of: lhs, fields },
+                    span: lhs_span.merge(input[*pointer - 1].span),
+                    ty: TypeId::default(),
+                })
+            }
+        };
+    }
+
     match &token.tt {
         tt @ TokenType::SLiteral { .. } => {
             lhs = Box::new(Expression {
                 et: ast::Expr::Literal { src: tt.clone() },
                 span: token.span,
+                ty: TypeId::default(),
             });
         }
         tt @ TokenType::ILiteral { .. } => {
             lhs = Box::new(Expression {
                 et: ast::Expr::Literal { src: tt.clone() },
                 span: token.span,
+                ty: TypeId::default(),
             });
         }
         tt @ TokenType::FLiteral { .. } => {
             lhs = Box::new(Expression {
                 et: ast::Expr::Literal { src: tt.clone() },
                 span: token.span,
+                ty: TypeId::default(),
             });
         }
         tt @ TokenType::BLiteral { .. } => {
             lhs = Box::new(Expression {
                 et: ast::Expr::Literal { src: tt.clone() },
                 span: token.span,
+                ty: TypeId::default(),
             });
         }
         TokenType::Enum => {
-            panic!("unimplemented");
+            let name_token = expect_token(input, pointer, accumulator, file, "an enum name", |tt| matches!(tt, TokenType::Ident(_)))?;
+            let name = if let TokenType::Ident(ref name) = name_token.tt { name.clone() } else { ice!("expect_token let something else through") };
+            expect!(TokenType::LCBrace, true, " after enum name");
+            let mut variants: Vec<ast::EnumVariant> = vec![];
+            loop {
+                if let Some(Token { tt: TokenType::RCBrace, .. }) = peek!() {
+                    break
+                }
+                let variant = expect_token(input, pointer, accumulator, file, "a variant name", |tt| matches!(tt, TokenType::Ident(_)))?;
+                let variant_name = if let TokenType::Ident(ref name) = variant.tt { name.clone() } else { ice!("expect_token let something else through") };
+                let payload = match peek!() {
+                    Some(Token { tt: TokenType::LParen, .. }) => {
+                        next!();
+                        let mut types = vec![];
+                        while !matches!(peek!(), Some(Token { tt: TokenType::RParen, .. })) {
+                            types.push(parse_type(input, pointer, accumulator, file)?);
+                            if let Some(Token { tt: TokenType::Comma, .. }) = peek!() {
+                                next!();
+                            } else {
+                                break
+                            }
+                        }
+                        expect!(TokenType::RParen, true, " after variant types");
+                        ast::EnumPayload::Tuple(types)
+                    },
+                    Some(Token { tt: TokenType::LCBrace, .. }) => {
+                        next!();
+                        ast::EnumPayload::Struct(parse_field_declarations(input, pointer, accumulator, file, "variant")?)
+                    },
+                    _ => ast::EnumPayload::Unit,
+                };
+                variants.push(ast::EnumVariant { name: variant_name, payload });
+                if let Some(Token { tt: TokenType::Comma, .. }) = peek!() {
+                    next!();
+                } else {
+                    break
+                }
+            }
+            expect!(TokenType::RCBrace, true, " after enum variants");
+            lhs = Box::new(Expression {
+                et: ast::Expr::EnumDecl { name, variants, slot: 0 },
+                span: token.span.merge(input[*pointer - 1].span),
+                ty: TypeId::default(),
+            })
+        },
+        TokenType::Struct => {
+            let name_token = expect_token(input, pointer, accumulator, file, "a struct name", |tt| matches!(tt, TokenType::Ident(_)))?;
+            let name = if let TokenType::Ident(ref name) = name_token.tt { name.clone() } else { ice!("expect_token let something else through") };
+            expect!(TokenType::LCBrace, true, " after struct name");
+            let fields = parse_field_declarations(input, pointer, accumulator, file, "struct")?;
+            lhs = Box::new(Expression {
+                et: ast::Expr::StructDecl { name, fields, slot: 0 },
+                span: token.span.merge(input[*pointer - 1].span),
+                ty: TypeId::default(),
+            })
         },
         TokenType::Fn => {
             let fn_name = if let Some(token) = next!() {
@@ -184,13 +572,16 @@ fn parse_impl(
             };
             expect!(TokenType::LParen, true, " after function name");
             let mut arguments: Vec<String> = vec![];
-            loop {
-                if let Some(Token { tt: TokenType::Ident(v), .. }) = peek!() {
+            let mut annotations: Vec<Option<ast::Type>> = vec![];
+            while let Some(Token { tt: TokenType::Ident(v), .. }) = peek!() {
+                next!();
+                arguments.push(v.clone());
+                annotations.push(if let Some(Token { tt: TokenType::Colon, .. }) = peek!() {
                     next!();
-                    arguments.push(v.clone());
+                    Some(parse_type(input, pointer, accumulator, file)?)
                 } else {
-                    break
-                }
+                    None
+                });
                 if let Some(Token { tt: TokenType::Comma, .. }) = peek!() {
                     next!();
                 } else {
@@ -198,13 +589,40 @@ fn parse_impl(
                 }
             }
             expect!(TokenType::RParen, true, " after function argument list");
+            let ret = if let Some(Token { tt: TokenType::Ret, .. }) = peek!() {
+                next!();
+                Some(parse_type(input, pointer, accumulator, file)?)
+            } else {
+                None
+            };
             expect!(TokenType::LCBrace, true, " after function arguments");
             *pointer -= 1;
             let block = parse_impl(input, precedence::ONE, pointer, accumulator, file, loader)?;
             let block_span = block.span;
             lhs = Box::new(Expression {
-                et: ast::Expr::Fn { name: fn_name, body: block, args: arguments },
-                span: token.span.merge(block_span)
+                et: ast::Expr::Fn { name: fn_name, body: block, args: arguments, annotations, ret, slot: 0 },
+                span: token.span.merge(block_span),
+                ty: TypeId::default(),
+            });
+        }
+        TokenType::Pipe => {
+            let mut arguments: Vec<String> = vec![];
+            while let Some(Token { tt: TokenType::Ident(v), .. }) = peek!() {
+                next!();
+                arguments.push(v.clone());
+                if let Some(Token { tt: TokenType::Comma, .. }) = peek!() {
+                    next!();
+                } else {
+                    break
+                }
+            }
+            expect!(TokenType::Pipe, true, " after lambda argument list");
+            let body = parse_impl(input, precedence::SEMICOLON + 1, pointer, accumulator, file, loader)?;
+            let body_span = body.span;
+            lhs = Box::new(Expression {
+                et: ast::Expr::Lambda { body, args: arguments },
+                span: token.span.merge(body_span),
+                ty: TypeId::default(),
             });
         }
         TokenType::Let => {
@@ -230,6 +648,14 @@ fn parse_impl(
                 ))
             };
             let mut span: Option<Span> = None;
+            let annotation = if let Some(Token { tt: TokenType::Colon, .. }) = peek!() {
+                next!();
+                let annotation = parse_type(input, pointer, accumulator, file)?;
+                span = Some(annotation.span);
+                Some(annotation)
+            } else {
+                None
+            };
             let value = if let Some(Token { tt: TokenType::Eq, .. }) = peek!() {
                 next!();
                 let value = parse_impl(input, precedence::ASSIGN, pointer, accumulator, file, loader)?;
@@ -239,16 +665,26 @@ fn parse_impl(
                 None
             };
             lhs = Box::new(Expression {
-                et: ast::Expr::Let { name, value },
-                span: if let Some(s) = span { token.span.merge(s) } else { token.span.merge(name_span) }
+                et: ast::Expr::Let { name, value, annotation, slot: 0 },
+                span: if let Some(s) = span { token.span.merge(s) } else { token.span.merge(name_span) },
+                ty: TypeId::default(),
             })
         }
-        TokenType::Ident(ident) => {
+        TokenType::Ident(ident) if ident == "_" => {
             lhs = Box::new(Expression {
-                et: ast::Expr::Identifier { id: ident.clone() },
+                et: ast::Expr::Placeholder,
                 span: token.span,
+                ty: TypeId::default(),
             })
         }
+        TokenType::Ident(ident) => {
+            lhs = Box::new(Expression {
+                et: ast::Expr::Identifier { id: ident.clone(), local: None },
+                span: token.span,
+                ty: TypeId::default(),
+            });
+            struct_literal!();
+        }
         TokenType::Not => {
             let right = parse_impl(input, precedence::UNARY, pointer, accumulator, file, loader)?;
             let right_span = right.span;
@@ -258,6 +694,7 @@ fn parse_impl(
                     right,
                 },
                 span: token.span.merge(right_span),
+                ty: TypeId::default(),
             })
         }
         TokenType::Show => {
@@ -269,6 +706,7 @@ fn parse_impl(
                     right,
                 },
                 span: token.span.merge(right_span),
+                ty: TypeId::default(),
             })
         }
         TokenType::Panic => {
@@ -280,6 +718,7 @@ fn parse_impl(
                     right,
                 },
                 span: token.span.merge(right_span),
+                ty: TypeId::default(),
             })
         }
         TokenType::Include => {
@@ -333,6 +772,7 @@ fn parse_impl(
                     right,
                 },
                 span: token.span.merge(right_span),
+                ty: TypeId::default(),
             })
         }
         TokenType::Break => {
@@ -343,17 +783,27 @@ fn parse_impl(
                 span = span.merge(expr.span);
                 maybe_right = Some(expr);
             }
-            lhs = Box::new(Expression { et: ast::Expr::Break { with: maybe_right }, span })
+            lhs = Box::new(Expression { et: ast::Expr::Break { with: maybe_right }, span, ty: TypeId::default() })
+        }
+        TokenType::Return => {
+            let mut maybe_right: Option<Box<Expression>> = None;
+            let mut span = token.span;
+            if has_expression!() {
+                let expr = parse_impl(input, precedence::BREAK, pointer, accumulator, file, loader)?;
+                span = span.merge(expr.span);
+                maybe_right = Some(expr);
+            }
+            lhs = Box::new(Expression { et: ast::Expr::Return { with: maybe_right }, span, ty: TypeId::default() })
         }
         TokenType::Continue => {
-            lhs = Box::new(Expression { et: ast::Expr::Continue, span: token.span })
+            lhs = Box::new(Expression { et: ast::Expr::Continue, span: token.span, ty: TypeId::default() })
         }
         TokenType::Loop => {
             expect!(TokenType::LCBrace, true, " after 'loop'");
             *pointer -= 1;
             let inside = parse_impl(input, precedence::ONE, pointer, accumulator, file, loader)?;
             let inside_span = inside.span;
-            lhs = Box::new(Expression { et: ast::Expr::Loop { inside }, span: token.span.merge(inside_span) })
+            lhs = Box::new(Expression { et: ast::Expr::Loop { inside }, span: token.span.merge(inside_span), ty: TypeId::default() })
         }
         TokenType::LParen => {
             lhs = parse_impl(input, 0, pointer, accumulator, file, loader)?;
@@ -399,7 +849,12 @@ fn parse_impl(
                 }
                 // *pointer -= 1;
             }
-            lhs = Box::new(Expression { et: ast::Expr::If { condition, then, else_ }, span: token.span.merge(then_span) })
+            lhs = Box::new(Expression { et: ast::Expr::If { condition, then, else_ }, span: token.span.merge(then_span), ty: TypeId::default() })
+        }
+        TokenType::Match => {
+            let value = parse_impl(input, 0, pointer, accumulator, file, loader)?;
+            let arms = match_arms!();
+            lhs = Box::new(Expression { et: ast::Expr::Match { value, arms }, span: token.span.merge(input[*pointer - 1].span), ty: TypeId::default() })
         }
         TokenType::While => {
             let condition = parse_impl(input, 0, pointer, accumulator, file, loader)?;
@@ -407,7 +862,7 @@ fn parse_impl(
             *pointer -= 1;
             let body = parse_impl(input, precedence::ONE, pointer, accumulator, file, loader)?;
             let body_span = body.span;
-            lhs = Box::new(Expression { et: ast::Expr::While { condition, body }, span: token.span.merge(body_span) })
+            lhs = Box::new(Expression { et: ast::Expr::While { condition, body }, span: token.span.merge(body_span), ty: TypeId::default() })
         }
         TokenType::LCBrace => {
             let inside = if has_expression!() { Some(parse_impl(input, 0, pointer, accumulator, file, loader)?) } else { None };
@@ -425,7 +880,8 @@ fn parse_impl(
                 } else {
                     lhs = Box::new(Expression {
                         et: ast::Expr::Block(inside),
-                        span: *span,
+                        span: token.span.merge(*span),
+                        ty: TypeId::default(),
                     });
                 }
             } else {
@@ -457,8 +913,9 @@ fn parse_impl(
                             );
                         } else {
                             lhs = Box::new(Expression {
-                                et: ast::Expr::Module(inside, name.clone()),
+                                et: ast::Expr::Module(inside, name.clone(), 0),
                                 span: *span,
+                                ty: TypeId::default(),
                             });
                         }
                     } else {
@@ -515,6 +972,7 @@ fn parse_impl(
                         left: lhs,
                         right: rhs,
                     },
+                    ty: TypeId::default(),
                 })
             }};
         }
@@ -536,6 +994,7 @@ fn parse_impl(
                         right: rhs,
                         op: $op
                     },
+                    ty: TypeId::default(),
                 })
             }}
         }
@@ -562,6 +1021,14 @@ fn parse_impl(
             TokenType::ExpEq   => infix_assign!(BinaryOp::Exp),
             TokenType::PlusEq  => infix_assign!(BinaryOp::Add),
             TokenType::MinusEq => infix_assign!(BinaryOp::Sub),
+            TokenType::As      => {
+                let to = parse_type(input, pointer, accumulator, file)?;
+                lhs = Box::new(Expression {
+                    span: lhs.span.merge(to.span),
+                    et: ast::Expr::Cast { value: lhs, to },
+                    ty: TypeId::default(),
+                })
+            },
             TokenType::Dot     => {
                 let maybe_token = next!();
                 let lhs_span = lhs.span;
@@ -571,7 +1038,16 @@ fn parse_impl(
                             object: lhs,
                             name: name.clone(),
                         },
-                        span: lhs_span.merge(*span)
+                        span: lhs_span.merge(*span),
+                        ty: TypeId::default(),
+                    });
+                    struct_literal!();
+                } else if let Some(Token { tt: TokenType::Match, .. }) = maybe_token {
+                    let arms = match_arms!();
+                    lhs = Box::new(Expression {
+                        et: ast::Expr::Match { value: lhs, arms },
+                        span: lhs_span.merge(input[*pointer - 1].span),
+                        ty: TypeId::default(),
                     })
                 } else {
                     *pointer -= 1;
@@ -593,10 +1069,68 @@ fn parse_impl(
                     }
                 };
                 expect!(TokenType::RParen, true, " after arguments");
+                // `f(_)` means `|x| f(x)`, while `f(_ * 2)` means `f(|x| x * 2)`
+                let mut bare_placeholders = vec![];
+                for (index, argument) in arguments.iter_mut().enumerate() {
+                    if matches!(argument.et, ast::Expr::Placeholder) {
+                        bare_placeholders.push(index);
+                        continue
+                    }
+                    let argument_span = argument.span;
+                    let mut found = vec![];
+                    find_placeholders(argument, &mut found, false);
+                    if found.len() > 1 {
+                        let mut error = make_error!(
+                            "ambiguous use of '_': this argument contains more than one placeholder",
+                            codes::E0017.0,
+                            Severity::Error,
+                            "in this argument" => argument_span
+                        );
+                        for placeholder in found {
+                            error = error.push("placeholder".to_owned(), placeholder.span);
+                        }
+                        accumulator.push(error);
+                    } else if found.len() == 1 {
+                        let wrapped = std::mem::replace(argument, Box::new(Expression { et: ast::Expr::Placeholder, span: argument_span, ty: TypeId::default() }));
+                        *argument = placeholder_lambda(wrapped);
+                    }
+                }
+                let call_span = lhs.span.merge(input[*pointer - 1].span);
+                if bare_placeholders.len() > 1 {
+                    let mut error = make_error!(
+                        "ambiguous use of '_': this call has more than one placeholder argument",
+                        codes::E0017.0,
+                        Severity::Error,
+                        "in this call" => call_span
+                    );
+                    for index in bare_placeholders.iter() {
+                        error = error.push("placeholder".to_owned(), arguments[*index].span);
+                    }
+                    accumulator.push(error);
+                }
+                let wrap_call = bare_placeholders.len() == 1;
+                if wrap_call {
+                    let mut found = vec![];
+                    find_placeholders(&mut lhs, &mut found, false);
+                    if let Some(placeholder) = found.first() {
+                        accumulator.push(make_error!(
+                            "ambiguous use of '_': both the function and one of its arguments are placeholders",
+                            codes::E0017.0,
+                            Severity::Error,
+                            "placeholder" => placeholder.span,
+                            "placeholder argument" => arguments[bare_placeholders[0]].span
+                        ));
+                    }
+                }
                 lhs = Box::new(Expression {
                     et: ast::Expr::Call { callee: lhs, args: arguments },
-                    span: token.span.merge(input[*pointer - 2].span)
-                })
+                    span: token.span.merge(input[*pointer - 2].span),
+                    ty: TypeId::default(),
+                });
+                if wrap_call {
+                    lhs.span = call_span;
+                    lhs = placeholder_lambda(lhs);
+                }
             },
             TokenType::Eq      => {
                 let rhs = parse_impl(
@@ -613,6 +1147,7 @@ fn parse_impl(
                         left: lhs,
                         right: rhs,
                     },
+                    ty: TypeId::default(),
                 })
             }
             TokenType::Semicolon => {
@@ -630,6 +1165,7 @@ fn parse_impl(
                         left: lhs,
                         right: rhs,
                     },
+                    ty: TypeId::default(),
                 })
             }
             _ => {
@@ -7,6 +7,7 @@ pub const LOGICAL: u8 = 10;
 // pub const EQ: u8 = 15;
 pub const ADD_SUB: u8 = 20;
 pub const MUL_DIV_EXP: u8 = 30;
+pub const AS: u8 = 35;
 pub const UNARY: u8 = 40;
 pub const CALL: u8 = 50;
 pub const DOT: u8 = 60;
@@ -36,6 +37,7 @@ pub fn get_precedence(t: &Option<&Token>) -> u8 {
         TokenType::Star      = MUL_DIV_EXP
         TokenType::Slash     = MUL_DIV_EXP
         TokenType::Exp       = MUL_DIV_EXP
+        TokenType::As        = AS
         TokenType::Semicolon = SEMICOLON
         TokenType::Eq        = ASSIGN
         TokenType::PlusEq    = ASSIGN
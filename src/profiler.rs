@@ -0,0 +1,98 @@
+use std::{cmp::Reverse, collections::HashMap, fs, path::Path, time::Duration};
+
+use crate::{
+    engine, errors,
+    failure::Failure,
+    loader::{self, Loader},
+    vm::{Cost, Profile, VM},
+};
+
+/// how many of the most expensive lines are shown
+const LINES_SHOWN: usize = 20;
+
+/// runs a file with profiling on, then prints where the time went.
+/// with `folded`, the call stacks are also written there in the folded format flamegraph tools read, weighted in nanoseconds
+pub fn profile_file<P: AsRef<Path>>(path: P, folded: Option<&Path>) -> Result<(), Failure> {
+    let mut loader = Loader::new();
+    let file = loader.load_file(path)?;
+    let program = match engine::compile(&mut loader, file, &[]) {
+        Ok((program, _)) => program,
+        Err(errors) => {
+            for error in errors.iter() {
+                errors::print_error(error, &loader);
+            }
+            return Ok(())
+        },
+    };
+    let mut vm = VM::new(program);
+    vm.enable_profiling();
+    if let Err(error) = vm.run() {
+        errors::print_error(&error.to_error(), &loader);
+    }
+    let Some(profile) = vm.take_profile() else {
+        return Ok(())
+    };
+    print_functions(&profile);
+    print_lines(&profile, &loader);
+    if let Some(folded) = folded {
+        fs::write(folded, folded_stacks(&profile)).map_err(Failure::IOError)?;
+    }
+    Ok(())
+}
+
+fn print_functions(profile: &Profile) {
+    let mut functions = HashMap::<&str, (Cost, Cost)>::new();
+    for (stack, cost) in profile.stacks.iter().zip(profile.stack_costs.iter()) {
+        let Some(function) = stack.last() else {
+            continue
+        };
+        add(&mut functions.entry(function).or_default().0, cost);
+        // a recursive function is only counted once per stack
+        let mut seen = vec![];
+        for function in stack {
+            if !seen.contains(&function) {
+                seen.push(function);
+                add(&mut functions.entry(function).or_default().1, cost);
+            }
+        }
+    }
+    let mut functions = functions.into_iter().collect::<Vec<_>>();
+    functions.sort_by_key(|(_, (own, _))| Reverse(own.time));
+    eprintln!("\x1B[32m{:>12} {:>12} {:>14}  function\x1B[0m", "self time", "total time", "instructions");
+    for (function, (own, total)) in functions {
+        eprintln!("{:>12} {:>12} {:>14}  {}", format_time(own.time), format_time(total.time), own.instructions, function);
+    }
+}
+
+fn print_lines(profile: &Profile, loader: &Loader) {
+    let mut line_starts = HashMap::new();
+    let mut lines = HashMap::<(usize, usize), Cost>::new();
+    for (span, cost) in profile.spans.iter() {
+        let starts = line_starts.entry(span.file).or_insert_with(|| loader.get_file(span.file).line_starts());
+        add(lines.entry((span.file, loader::line_of(starts, span.start))).or_default(), cost);
+    }
+    let mut lines = lines.into_iter().collect::<Vec<_>>();
+    lines.sort_by_key(|(_, cost)| Reverse(cost.time));
+    eprintln!("\n\x1B[32m{:>12} {:>14}  line\x1B[0m", "time", "instructions");
+    for ((file, line), cost) in lines.into_iter().take(LINES_SHOWN) {
+        let source = loader.get_file(file);
+        let text = source.string().lines().nth(line).unwrap_or("").trim();
+        eprintln!("{:>12} {:>14}  {}:{}  \x1B[37m{}\x1B[0m", format_time(cost.time), cost.instructions, source.src().unwrap_or("<unknown>"), line + 1, text);
+    }
+}
+
+/// `outermost;...;innermost weight`, one line per stack
+fn folded_stacks(profile: &Profile) -> String {
+    profile.stacks.iter().zip(profile.stack_costs.iter()).filter(|(_, cost)| cost.instructions > 0).map(|(stack, cost)| {
+        format!("{} {}\n", stack.join(";"), cost.time.as_nanos())
+    }).collect()
+}
+
+fn add(total: &mut Cost, cost: &Cost) {
+    total.instructions += cost.instructions;
+    total.time += cost.time;
+}
+
+fn format_time(time: Duration) -> String {
+    format!("{:.3}ms", time.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,184 @@
+use crate::{ast::{self, Expression, Expr, Local, Pattern, Pat, PatPayload}, errors::{codes, make_error, Error, Severity}, ice::ice, loader::Span};
+
+/// binds every identifier that refers to a lexically visible declaration to a `Local`,
+/// and gives every declaration the slot it occupies in its scope.
+/// the scopes here have to line up with the `NewScope`s the compiler emits
+pub fn resolve(expr: &mut Expression) -> Result<(), Vec<Error>> {
+    resolve_in_scopes(expr, &[])
+}
+
+/// like `resolve`, for a program that will run under `scopes`, innermost last, with the names each of them
+/// already declares by slot. the top level goes on declaring in the last one
+pub fn resolve_in_scopes(expr: &mut Expression, scopes: &[Vec<String>]) -> Result<(), Vec<Error>> {
+    let scopes = if scopes.is_empty() { vec![vec![]] } else { scopes.to_vec() };
+    let ids = (0..scopes.len()).collect();
+    let mut resolver = Resolver { next_id: scopes.len(), scopes, ids, pending: vec![], unresolved: vec![] };
+    resolver.resolve(expr);
+    while !resolver.scopes.is_empty() {
+        resolver.pop_scope();
+    }
+    resolver.unresolved.sort_by_key(|assignment| (assignment.span.file, assignment.span.start));
+    let errors = resolver.unresolved.into_iter().map(|assignment| {
+        let suggestion = format!("use `let {} = ...` to declare it", assignment.name);
+        make_error!(
+            format!("assignment to undeclared variable {}", assignment.name),
+            codes::E0021.0,
+            Severity::Error,
+            None => assignment.span,
+            suggestion.as_str() => assignment.target
+        )
+    }).collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[derive(Debug)]
+struct Resolver {
+    /// the names declared in each scope, indexed by slot
+    scopes: Vec<Vec<String>>,
+    /// a number for each scope in `scopes` that no other scope gets
+    ids: Vec<usize>,
+    next_id: usize,
+    /// assignments to names that weren't declared yet, which are fine as long as a scope around them declares the name later
+    pending: Vec<Assignment>,
+    unresolved: Vec<Assignment>,
+}
+
+#[derive(Debug)]
+struct Assignment {
+    name: String,
+    span: Span,
+    target: Span,
+    /// the ids of the scopes around the assignment
+    scopes: Vec<usize>,
+}
+
+impl Resolver {
+    fn declare(&mut self, name: &str) -> usize {
+        let scope = self.scopes.last_mut().unwrap_or_else(|| ice!("there should always be a scope"));
+        scope.push(name.to_owned());
+        scope.len() - 1
+    }
+
+    fn lookup(&self, name: &str) -> Option<Local> {
+        self.scopes.iter().rev().enumerate().find_map(|(depth, scope)| {
+            // the latest declaration wins when a name is shadowed within the same scope
+            scope.iter().rposition(|declared| declared == name).map(|slot| Local { depth, slot })
+        })
+    }
+
+    fn push_scope(&mut self, names: Vec<String>) {
+        self.scopes.push(names);
+        self.ids.push(self.next_id);
+        self.next_id += 1;
+    }
+
+    /// the assignments waiting for this scope are resolved if it ended up declaring their name,
+    /// the ones it was the last chance for aren't
+    fn pop_scope(&mut self) {
+        let (Some(names), Some(id)) = (self.scopes.pop(), self.ids.pop()) else { ice!("popped more scopes than were pushed") };
+        let pending = std::mem::take(&mut self.pending);
+        for assignment in pending {
+            if !assignment.scopes.contains(&id) {
+                self.pending.push(assignment)
+            } else if !names.contains(&assignment.name) {
+                if assignment.scopes[0] == id {
+                    self.unresolved.push(assignment)
+                } else {
+                    self.pending.push(assignment)
+                }
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, args: &[String], body: &mut Expression) {
+        self.push_scope(args.to_vec());
+        self.resolve(body);
+        self.pop_scope();
+    }
+
+    fn resolve(&mut self, expr: &mut Expression) {
+        let span = expr.span;
+        match &mut expr.et {
+            Expr::Identifier { id, local } => *local = self.lookup(id),
+            Expr::Block(Some(inside)) => {
+                self.push_scope(vec![]);
+                self.resolve(inside);
+                self.pop_scope();
+            },
+            Expr::Module(inside, name, slot) => {
+                self.push_scope(vec![]);
+                if let Some(inside) = inside {
+                    self.resolve(inside);
+                }
+                self.pop_scope();
+                *slot = self.declare(name);
+            },
+            Expr::Fn { name, body, args, slot, .. } => {
+                // declared first so that the function can call itself
+                *slot = self.declare(name);
+                self.resolve_function(args, body);
+            },
+            Expr::Lambda { body, args } => self.resolve_function(args, body),
+            Expr::Let { name, value, slot, .. } => {
+                if let Some(value) = value {
+                    self.resolve(value);
+                }
+                *slot = self.declare(name);
+            },
+            Expr::Assign { left, right } | Expr::AssignOp { left, right, .. } => {
+                self.resolve(left);
+                self.resolve(right);
+                if let Expr::Identifier { id, local: None } = &left.et {
+                    self.pending.push(Assignment { name: id.clone(), span, target: left.span, scopes: self.ids.clone() });
+                }
+            },
+            Expr::EnumDecl { name, slot, .. } | Expr::StructDecl { name, slot, .. } => *slot = self.declare(name),
+            Expr::Match { value, arms } => {
+                self.resolve(value);
+                for arm in arms {
+                    // the patterns are tested before the arm's scope exists
+                    self.resolve_pattern_paths(&mut arm.pattern);
+                    self.push_scope(vec![]);
+                    self.declare_pattern_bindings(&mut arm.pattern);
+                    self.resolve(&mut arm.body);
+                    self.pop_scope();
+                }
+            },
+            _ => for child in ast::children_mut(expr) {
+                self.resolve(child)
+            },
+        }
+    }
+
+    fn resolve_pattern_paths(&mut self, pattern: &mut Pattern) {
+        if let Pat::Variant { path, payload } = &mut pattern.pt {
+            self.resolve(path);
+            for item in payload_items(payload) {
+                self.resolve_pattern_paths(item)
+            }
+        }
+    }
+
+    /// in the same order as `compiler::lower_pattern_bindings` declares them
+    fn declare_pattern_bindings(&mut self, pattern: &mut Pattern) {
+        match &mut pattern.pt {
+            Pat::Binding { name, slot } => *slot = self.declare(name),
+            Pat::Variant { payload, .. } => for item in payload_items(payload) {
+                self.declare_pattern_bindings(item)
+            },
+            Pat::Literal { .. } | Pat::Wildcard => (),
+        }
+    }
+}
+
+fn payload_items(payload: &mut PatPayload) -> Vec<&mut Pattern> {
+    match payload {
+        PatPayload::Unit => vec![],
+        PatPayload::Tuple(items) => items.iter_mut().collect(),
+        PatPayload::Struct(fields) => fields.iter_mut().map(|(_, item)| item).collect(),
+    }
+}
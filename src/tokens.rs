@@ -26,8 +26,8 @@ pub enum ILiteralTypeHint {
     I32,
     U64,
     I64,
-    // U128,
-    // I128, (not for now)
+    U128,
+    I128,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -73,6 +73,7 @@ pub enum TokenType {
     Star,
     Slash,
     Ret,
+    FatArrow,
     Return,
     Exp,
     Semicolon,
@@ -93,6 +94,7 @@ pub enum TokenType {
     ExpEq,
     BLiteral(bool),
     Comma,
+    Pipe,
     Loop,
     While,
     Use,
@@ -135,6 +137,7 @@ impl TokenType {
             Star            => "'*'",
             Slash           => "'/'",
             Ret             => "'->'",
+            FatArrow        => "'=>'",
             Return          => "'return'",
             Exp             => "'**'",
             Semicolon       => "';'",
@@ -155,6 +158,7 @@ impl TokenType {
             ExpEq           => "'**='",
             BLiteral(_)     => "<boolean literal>",
             Comma           => "','",
+            Pipe            => "'|'",
             Loop            => "'loop'",
             While           => "'while'",
             Use             => "'use'",
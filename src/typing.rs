@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// the parser gives every expression the default one, `inference::infer` gives them their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct TypeId(usize);
 
 #[derive(Debug, Clone)]
@@ -9,16 +10,49 @@ pub enum Type {
     U16,
     U32,
     U64,
+    U128,
     I8,
     I16,
     I32,
     I64,
+    I128,
     F32,
     F64,
     Char,
     Bool,
+    Str,
+    Nothing,
     Ref(Box<Type>),
     RefMut(Box<Type>),
+    Function(Vec<Type>, Box<Type>),
+    /// modules, enums, structs and everything else that is only checked at runtime
+    Dynamic,
+}
+
+impl Type {
+    pub fn integer(&self) -> Option<Integer> {
+        Some(match self {
+            Type::U8 => Integer::U8,
+            Type::U16 => Integer::U16,
+            Type::U32 => Integer::U32,
+            Type::U64 => Integer::U64,
+            Type::U128 => Integer::U128,
+            Type::I8 => Integer::I8,
+            Type::I16 => Integer::I16,
+            Type::I32 => Integer::I32,
+            Type::I64 => Integer::I64,
+            Type::I128 => Integer::I128,
+            _ => return None,
+        })
+    }
+
+    pub fn float(&self) -> Option<Float> {
+        match self {
+            Type::F32 => Some(Float::F32),
+            Type::F64 => Some(Float::F64),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -27,10 +61,45 @@ pub enum Integer {
     U16,
     U32,
     U64,
+    U128,
     I8,
     I16,
     I32,
     I64,
+    I128,
+}
+
+impl Integer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Integer::U8 => "u8",
+            Integer::U16 => "u16",
+            Integer::U32 => "u32",
+            Integer::U64 => "u64",
+            Integer::U128 => "u128",
+            Integer::I8 => "i8",
+            Integer::I16 => "i16",
+            Integer::I32 => "i32",
+            Integer::I64 => "i64",
+            Integer::I128 => "i128",
+        }
+    }
+
+    /// (min, max), each in the widest type that can hold it for every integer type
+    pub fn range(self) -> (i128, u128) {
+        match self {
+            Integer::U8 => (u8::MIN.into(), u8::MAX.into()),
+            Integer::U16 => (u16::MIN.into(), u16::MAX.into()),
+            Integer::U32 => (u32::MIN.into(), u32::MAX.into()),
+            Integer::U64 => (u64::MIN.into(), u64::MAX.into()),
+            Integer::U128 => (u128::MIN as i128, u128::MAX),
+            Integer::I8 => (i8::MIN.into(), i8::MAX as u128),
+            Integer::I16 => (i16::MIN.into(), i16::MAX as u128),
+            Integer::I32 => (i32::MIN.into(), i32::MAX as u128),
+            Integer::I64 => (i64::MIN.into(), i64::MAX as u128),
+            Integer::I128 => (i128::MIN, i128::MAX as u128),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -44,9 +113,18 @@ pub enum TypeInfo {
     // built-in types
     Integer(Option<Integer>),
     Float(Option<Float>),
+    /// an integer literal without a suffix, which can still turn out to be a float
+    Number,
     Char,
     Bool,
-    
+    Str,
+    Nothing,
+    Function(Vec<TypeId>, TypeId),
+    Dynamic,
+    /// a struct or an enum, or a type the machine can't look inside like an array, by how it's written.
+    /// only the same name and dynamic values fit
+    Named(String),
+
     // references
     RefTo(TypeId),
     RefMutTo(TypeId),
@@ -70,26 +148,149 @@ impl Machine {
         id
     }
 
+    /// follows `TypeInfo::Ref`s to the variable that actually holds the type
+    pub fn find(&self, id: TypeId) -> TypeId {
+        match self.vars[&id] {
+            TypeInfo::Ref(id) => self.find(id),
+            _ => id,
+        }
+    }
+
+    pub fn info(&self, id: TypeId) -> &TypeInfo {
+        &self.vars[&self.find(id)]
+    }
+
+    /// the error reads as if `a` was expected and `b` was found
     pub fn unify(&mut self, a: TypeId, b: TypeId) -> Result<(), String> {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return Ok(())
+        }
         match (self.vars[&a].clone(), self.vars[&b].clone()) {
-            (TypeInfo::Ref(a), _) => self.unify(a, b),
-            (_, TypeInfo::Ref(a)) => self.unify(a, b),
-
+            (TypeInfo::Unknown, _) if self.occurs(a, b) => Err(format!("{} would have to contain itself", self.describe(b))),
+            (_, TypeInfo::Unknown) if self.occurs(b, a) => Err(format!("{} would have to contain itself", self.describe(a))),
             (TypeInfo::Unknown, _) => { self.vars.insert(a, TypeInfo::Ref(b)); Ok(()) },
             (_, TypeInfo::Unknown) => { self.vars.insert(b, TypeInfo::Ref(a)); Ok(()) },
 
+            // whatever a dynamic value is used as is checked at runtime
+            (TypeInfo::Dynamic, TypeInfo::Function(arguments, ret)) | (TypeInfo::Function(arguments, ret), TypeInfo::Dynamic) => {
+                let dynamic = if let TypeInfo::Dynamic = self.vars[&a] { a } else { b };
+                for argument in arguments {
+                    self.unify(dynamic, argument)?;
+                }
+                self.unify(dynamic, ret)
+            },
+            (TypeInfo::Dynamic, _) | (_, TypeInfo::Dynamic) => Ok(()),
+
             (TypeInfo::Bool, TypeInfo::Bool) => Ok(()),
-            (TypeInfo::Integer(Some(a)), TypeInfo::Integer(Some(b))) if a == b => Ok(()),
-            (TypeInfo::Float(Some(a)), TypeInfo::Float(Some(b))) if a == b => Ok(()),
-            (TypeInfo::Integer(Some(_)), TypeInfo::Integer(None)) => { self.vars.insert(a, TypeInfo::Ref(b)); Ok(()) },
-            (TypeInfo::Integer(None), TypeInfo::Integer(Some(_))) => { self.vars.insert(b, TypeInfo::Ref(a)); Ok(()) },
-            (TypeInfo::Float(Some(_)), TypeInfo::Float(None)) => { self.vars.insert(a, TypeInfo::Ref(b)); Ok(()) },
-            (TypeInfo::Float(None), TypeInfo::Float(Some(_))) => { self.vars.insert(b, TypeInfo::Ref(a)); Ok(()) },
+            (TypeInfo::Char, TypeInfo::Char) => Ok(()),
+            (TypeInfo::Str, TypeInfo::Str) => Ok(()),
+            (TypeInfo::Nothing, TypeInfo::Nothing) => Ok(()),
+            (TypeInfo::Named(x), TypeInfo::Named(y)) if x == y => Ok(()),
+            (TypeInfo::Integer(Some(x)), TypeInfo::Integer(Some(y))) if x == y => Ok(()),
+            (TypeInfo::Float(Some(x)), TypeInfo::Float(Some(y))) if x == y => Ok(()),
+            (TypeInfo::Integer(_), TypeInfo::Integer(None))
+          | (TypeInfo::Float(_), TypeInfo::Float(None))
+          | (TypeInfo::Integer(_) | TypeInfo::Float(_) | TypeInfo::Number, TypeInfo::Number) => { self.vars.insert(b, TypeInfo::Ref(a)); Ok(()) },
+            (TypeInfo::Integer(None) | TypeInfo::Number, TypeInfo::Integer(_) | TypeInfo::Float(_))
+          | (TypeInfo::Float(None), TypeInfo::Float(_)) => { self.vars.insert(a, TypeInfo::Ref(b)); Ok(()) },
 
             (TypeInfo::RefTo(a_item), TypeInfo::RefTo(b_item)) => self.unify(a_item, b_item),
             (TypeInfo::RefMutTo(a_item), TypeInfo::RefMutTo(b_item)) => self.unify(a_item, b_item),
+            (TypeInfo::Function(a_arguments, a_ret), TypeInfo::Function(b_arguments, b_ret)) if a_arguments.len() == b_arguments.len() => {
+                for (a_argument, b_argument) in a_arguments.into_iter().zip(b_arguments) {
+                    self.unify(a_argument, b_argument)?;
+                }
+                self.unify(a_ret, b_ret)
+            },
+
+            _ => Err(format!("expected {}, found {}", self.describe(a), self.describe(b))),
+        }
+    }
+
+    /// whether `var` shows up anywhere in `id`
+    fn occurs(&self, var: TypeId, id: TypeId) -> bool {
+        let id = self.find(id);
+        id == var || match &self.vars[&id] {
+            TypeInfo::RefTo(item) | TypeInfo::RefMutTo(item) => self.occurs(var, *item),
+            TypeInfo::Function(arguments, ret) => arguments.iter().any(|argument| self.occurs(var, *argument)) || self.occurs(var, *ret),
+            _ => false,
+        }
+    }
+
+    /// unifies `id` with a number if nothing is known about it yet
+    pub fn require_number(&mut self, id: TypeId) -> Result<(), String> {
+        match self.info(id) {
+            TypeInfo::Unknown => {
+                let number = self.insert(TypeInfo::Number);
+                self.unify(number, id)
+            },
+            TypeInfo::Integer(_) | TypeInfo::Float(_) | TypeInfo::Number | TypeInfo::Dynamic => Ok(()),
+            _ => Err(format!("expected a number, found {}", self.describe(id))),
+        }
+    }
 
-            (a, b) => Err(format!("Conflict between {:?} and {:?}", a, b)),
+    /// the variables in `id` that nothing is known about, each one once
+    pub fn free_variables(&self, id: TypeId, found: &mut Vec<TypeId>) {
+        let id = self.find(id);
+        match &self.vars[&id] {
+            TypeInfo::Unknown if !found.contains(&id) => found.push(id),
+            TypeInfo::RefTo(item) | TypeInfo::RefMutTo(item) => self.free_variables(*item, found),
+            TypeInfo::Function(arguments, ret) => {
+                for argument in arguments {
+                    self.free_variables(*argument, found)
+                }
+                self.free_variables(*ret, found)
+            },
+            _ => (),
+        }
+    }
+
+    /// a copy of `id` where every variable in `generic` is replaced by a new one
+    pub fn instantiate(&mut self, id: TypeId, generic: &[TypeId]) -> TypeId {
+        let mut replacements = HashMap::new();
+        self.instantiate_impl(id, generic, &mut replacements)
+    }
+
+    fn instantiate_impl(&mut self, id: TypeId, generic: &[TypeId], replacements: &mut HashMap<TypeId, TypeId>) -> TypeId {
+        let id = self.find(id);
+        match self.vars[&id].clone() {
+            TypeInfo::Unknown if generic.contains(&id) => *replacements.entry(id).or_insert_with(|| self.insert(TypeInfo::Unknown)),
+            TypeInfo::RefTo(item) => {
+                let item = self.instantiate_impl(item, generic, replacements);
+                self.insert(TypeInfo::RefTo(item))
+            },
+            TypeInfo::RefMutTo(item) => {
+                let item = self.instantiate_impl(item, generic, replacements);
+                self.insert(TypeInfo::RefMutTo(item))
+            },
+            TypeInfo::Function(arguments, ret) => {
+                let arguments = arguments.into_iter().map(|argument| self.instantiate_impl(argument, generic, replacements)).collect();
+                let ret = self.instantiate_impl(ret, generic, replacements);
+                self.insert(TypeInfo::Function(arguments, ret))
+            },
+            _ => id,
+        }
+    }
+
+    /// how `id` is shown in diagnostics, with `_` for what isn't known yet
+    pub fn describe(&self, id: TypeId) -> String {
+        match self.info(id) {
+            TypeInfo::Integer(Some(integer)) => format!("{:?}", integer).to_lowercase(),
+            TypeInfo::Integer(None) => "{integer}".to_owned(),
+            TypeInfo::Float(Some(float)) => format!("{:?}", float).to_lowercase(),
+            TypeInfo::Float(None) => "{float}".to_owned(),
+            TypeInfo::Number => "{number}".to_owned(),
+            TypeInfo::Char => "char".to_owned(),
+            TypeInfo::Bool => "bool".to_owned(),
+            TypeInfo::Str => "str".to_owned(),
+            TypeInfo::Nothing => "nothing".to_owned(),
+            TypeInfo::Function(arguments, ret) => format!("fn({}) -> {}", arguments.iter().map(|argument| self.describe(*argument)).collect::<Vec<_>>().join(", "), self.describe(*ret)),
+            TypeInfo::Dynamic => "dynamic".to_owned(),
+            TypeInfo::Named(name) => name.clone(),
+            TypeInfo::RefTo(item) => format!("&{}", self.describe(*item)),
+            TypeInfo::RefMutTo(item) => format!("&mut {}", self.describe(*item)),
+            TypeInfo::Ref(_) | TypeInfo::Unknown => "_".to_owned(),
         }
     }
 
@@ -100,17 +301,24 @@ impl Machine {
             }
         }
 
-        match self.vars[&id] {
-            TypeInfo::Unknown => Err(format!("Cannot infer")),
-            TypeInfo::Ref(id) => self.reconstruct(id),
+        match &self.vars[&id] {
+            TypeInfo::Unknown => Err("cannot infer".to_owned()),
+            TypeInfo::Ref(id) => self.reconstruct(*id),
             TypeInfo::Bool => Ok(Type::Bool),
             TypeInfo::Char => Ok(Type::Char),
-            TypeInfo::RefTo(id) => Ok(Type::Ref(Box::new(self.reconstruct(id)?))),
-            TypeInfo::RefMutTo(id) => Ok(Type::RefMut(Box::new(self.reconstruct(id)?))),
-            TypeInfo::Integer(Some(i)) => Ok(num_match_arms!(i, Integer, U8 U16 U32 U64 I8 I16 I32 I64)),
+            TypeInfo::Str => Ok(Type::Str),
+            TypeInfo::Nothing => Ok(Type::Nothing),
+            TypeInfo::Dynamic | TypeInfo::Named(_) => Ok(Type::Dynamic),
+            TypeInfo::RefTo(id) => Ok(Type::Ref(Box::new(self.reconstruct(*id)?))),
+            TypeInfo::RefMutTo(id) => Ok(Type::RefMut(Box::new(self.reconstruct(*id)?))),
+            TypeInfo::Function(arguments, ret) => Ok(Type::Function(
+                arguments.iter().map(|argument| self.reconstruct(*argument)).collect::<Result<_, _>>()?,
+                Box::new(self.reconstruct(*ret)?),
+            )),
+            TypeInfo::Integer(Some(i)) => Ok(num_match_arms!(i, Integer, U8 U16 U32 U64 U128 I8 I16 I32 I64 I128)),
             TypeInfo::Float(Some(i)) => Ok(num_match_arms!(i, Float, F32 F64)),
-            TypeInfo::Integer(None) => Ok(Type::I32),
+            TypeInfo::Integer(None) | TypeInfo::Number => Ok(Type::I32),
             TypeInfo::Float(None) => Ok(Type::F64),
         }
     }
-}
\ No newline at end of file
+}
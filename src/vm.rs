@@ -1,4 +1,6 @@
-use std::{collections::HashMap, rc::Rc, cell::RefCell};
+use std::{rc::{Rc, Weak}, cell::RefCell, cmp::Ordering, collections::{HashMap, HashSet}, fmt, time::{Duration, Instant}};
+
+use crate::{errors::{codes, Error, Severity}, loader::Span, typing::{Float, Integer}};
 
 use crate::ice::ice;
 
@@ -7,6 +9,8 @@ pub struct Program {
     pub instructions: Vec<Instruction>,
     pub constants: Vec<Value>,
     pub names: Vec<String>,
+    /// the span of the expression each instruction was generated from
+    pub spans: Vec<Option<Span>>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,7 +20,6 @@ pub enum Instruction {
     Discard,
     PushNothing,
     Duplicate,
-    PushJumpRef(usize),
     Swap,
 
     // control flow
@@ -25,8 +28,12 @@ pub enum Instruction {
     Terminate,
     RequireArguments(usize),
     Null,
-    Call,
+    /// calls the value on top of the stack with the given number of arguments below it
+    Call(usize),
     Panic,
+    MakeClosure,
+    MatchFailed,
+    IsVariant,
 
     // operations on values
     Negate,
@@ -36,6 +43,11 @@ pub enum Instruction {
     Divide,
     Invert,
     RaiseTo,
+    ConvertToInteger(Integer),
+    ConvertToFloat(Float),
+    /// converts the number this many values below the top (0 or 1), a literal whose type is only known at runtime,
+    /// to the type of the other one of the top two
+    ConvertLiteral(usize),
     CheckEquality,
     Lesser,
     Greater,
@@ -44,152 +56,1025 @@ pub enum Instruction {
     CheckInequality,
     Show,
     AccessProperty(usize),
+    Construct(usize),
+    StoreProperty(usize),
+    PayloadItem(usize),
 
     // scoping & variables
     NewScope,
+    /// (depth, slot), as found by the resolver
+    LoadLocal(usize, usize),
+    StoreLocal(usize, usize),
+    /// (slot, name) in the innermost scope
+    DeclareLocal(usize, usize),
+    /// for names the resolver couldn't bind, looked up through every scope at runtime
     LoadVar(usize),
     Store(usize),
-    AssignStore(usize),
     EndScope,
-    EndAndNameScope(usize),
+    /// ends the innermost scope and pushes it as a module
+    EndModuleScope,
 
     // functions
-    PopJump,
+    Return,
 
     // for transformation into other instructions
     JumpTo(usize),
     ConditionalJumpTo(usize),
     JumpTarget(usize),
-    JumpRefTo(usize),
 
     // debug
     FunctionTag(usize),
-    CodegenHelper(usize),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
-    Num(f64),
+    Num(Number),
     Str(String),
     Bool(bool),
     Fn(usize),
-    JumpRef(usize),
+    Closure(Rc<Closure>),
+    Native(Rc<NativeFn>),
     Nothing,
     Scope(Rc<RefCell<Scope>>),
+    Enum(Rc<EnumDef>),
+    Variant(Rc<Variant>),
+    Constructor(Rc<EnumDef>, usize),
+    StructDef(Rc<StructDef>),
+    Struct(Rc<RefCell<Struct>>),
+}
+
+/// a number along with its type, one variant for each of `typing::Integer` and `typing::Float`.
+/// arithmetic only happens between numbers of the same type, `as` converts between them
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Number {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+}
+
+/// what integer arithmetic does when the result doesn't fit in its type
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overflow {
+    /// stops the program with `RuntimeErrorKind::Overflow`
+    #[default]
+    Checked,
+    Wrapping,
+}
+
+/// `$integer` gets the two values when they're integers of the same type and gives an `Option` back, `None` meaning overflow.
+/// `$float` gets them when they're floats of the same type
+macro_rules! same_type {
+    ($lhs:expr, $rhs:expr, |$a:ident, $b:ident| $integer:expr, $float:expr) => {
+        match ($lhs, $rhs) {
+            // most numbers are i32s, this keeps them from going through every other pair first
+            (Number::I32($a), Number::I32($b)) => $integer.map(Number::I32),
+            (Number::U8($a), Number::U8($b)) => $integer.map(Number::U8),
+            (Number::U16($a), Number::U16($b)) => $integer.map(Number::U16),
+            (Number::U32($a), Number::U32($b)) => $integer.map(Number::U32),
+            (Number::U64($a), Number::U64($b)) => $integer.map(Number::U64),
+            (Number::U128($a), Number::U128($b)) => $integer.map(Number::U128),
+            (Number::I8($a), Number::I8($b)) => $integer.map(Number::I8),
+            (Number::I16($a), Number::I16($b)) => $integer.map(Number::I16),
+            (Number::I64($a), Number::I64($b)) => $integer.map(Number::I64),
+            (Number::I128($a), Number::I128($b)) => $integer.map(Number::I128),
+            (Number::F32($a), Number::F32($b)) => Some(Number::F32($float)),
+            (Number::F64($a), Number::F64($b)) => Some(Number::F64($float)),
+            (lhs, rhs) => return Err(mixed(lhs, rhs)),
+        }
+    };
+}
+
+/// `$integer` gets the value of any integer, `$float` gets floats as an `f64`
+macro_rules! any_type {
+    ($number:expr, |$x:ident| $integer:expr, |$f:ident| $float:expr) => {
+        match $number {
+            Number::U8($x) => $integer,
+            Number::U16($x) => $integer,
+            Number::U32($x) => $integer,
+            Number::U64($x) => $integer,
+            Number::U128($x) => $integer,
+            Number::I8($x) => $integer,
+            Number::I16($x) => $integer,
+            Number::I32($x) => $integer,
+            Number::I64($x) => $integer,
+            Number::I128($x) => $integer,
+            Number::F32($f) => {
+                let $f = f64::from($f);
+                $float
+            },
+            Number::F64($f) => $float,
+        }
+    };
+}
+
+/// `None` if the value doesn't fit, works for a value of any integer type
+macro_rules! integer_try_from {
+    ($value:expr, $to:expr) => {
+        match $to {
+            Integer::U8 => u8::try_from($value).ok().map(Number::U8),
+            Integer::U16 => u16::try_from($value).ok().map(Number::U16),
+            Integer::U32 => u32::try_from($value).ok().map(Number::U32),
+            Integer::U64 => u64::try_from($value).ok().map(Number::U64),
+            Integer::U128 => u128::try_from($value).ok().map(Number::U128),
+            Integer::I8 => i8::try_from($value).ok().map(Number::I8),
+            Integer::I16 => i16::try_from($value).ok().map(Number::I16),
+            Integer::I32 => i32::try_from($value).ok().map(Number::I32),
+            Integer::I64 => i64::try_from($value).ok().map(Number::I64),
+            Integer::I128 => i128::try_from($value).ok().map(Number::I128),
+        }
+    };
+}
+
+/// wraps around integers and saturates floats, like rust's `as`
+macro_rules! integer_as {
+    ($value:expr, $to:expr) => {
+        match $to {
+            Integer::U8 => Number::U8($value as u8),
+            Integer::U16 => Number::U16($value as u16),
+            Integer::U32 => Number::U32($value as u32),
+            Integer::U64 => Number::U64($value as u64),
+            Integer::U128 => Number::U128($value as u128),
+            Integer::I8 => Number::I8($value as i8),
+            Integer::I16 => Number::I16($value as i16),
+            Integer::I32 => Number::I32($value as i32),
+            Integer::I64 => Number::I64($value as i64),
+            Integer::I128 => Number::I128($value as i128),
+        }
+    };
+}
+
+/// kept out of the way of the arithmetic, which almost never gets here
+#[cold]
+fn mixed(lhs: Number, rhs: Number) -> RuntimeErrorKind {
+    RuntimeErrorKind::MixedNumbers(lhs, rhs)
+}
+
+impl Number {
+    pub fn type_name(self) -> &'static str {
+        match self {
+            Number::F32(_) => "f32",
+            Number::F64(_) => "f64",
+            integer => integer.integer_type().name(),
+        }
+    }
+
+    /// `None` if it doesn't fit and `overflow` is `Overflow::Checked`, wrapping around otherwise
+    pub fn from_integer(value: i128, to: Integer, overflow: Overflow) -> Option<Number> {
+        match overflow {
+            Overflow::Checked => integer_try_from!(value, to),
+            Overflow::Wrapping => Some(integer_as!(value, to)),
+        }
+    }
+
+    /// like `from_integer`, for the values only a `u128` can hold
+    pub fn from_unsigned(value: u128, to: Integer, overflow: Overflow) -> Option<Number> {
+        match overflow {
+            Overflow::Checked => integer_try_from!(value, to),
+            Overflow::Wrapping => Some(integer_as!(value, to)),
+        }
+    }
+
+    pub fn from_float(value: f64, to: Float) -> Number {
+        match to {
+            Float::F32 => Number::F32(value as f32),
+            Float::F64 => Number::F64(value),
+        }
+    }
+
+    /// floats are rounded towards zero first. when they still don't fit, wrapping saturates like rust's `as`
+    pub fn to_integer(self, to: Integer, overflow: Overflow) -> Result<Number, RuntimeErrorKind> {
+        let converted = any_type!(self, |x| match overflow {
+            Overflow::Checked => integer_try_from!(x, to),
+            Overflow::Wrapping => Some(integer_as!(x, to)),
+        }, |x| {
+            let (min, max) = to.range();
+            // `max as f64` can round up to the next power of two, which doesn't fit anymore
+            let fits = x.is_finite() && x.trunc() >= min as f64 && x.trunc() < max as f64 + 1.0;
+            (fits || overflow == Overflow::Wrapping).then_some(integer_as!(x, to))
+        });
+        converted.ok_or_else(|| RuntimeErrorKind::InvalidConversion(self, to.name()))
+    }
+
+    pub fn to_float(self, to: Float) -> Number {
+        Number::from_float(any_type!(self, |x| x as f64, |x| x), to)
+    }
+
+    pub fn add(self, rhs: Number, overflow: Overflow) -> Result<Number, RuntimeErrorKind> {
+        same_type!(self, rhs, |a, b| match overflow {
+            Overflow::Checked => a.checked_add(b),
+            Overflow::Wrapping => Some(a.wrapping_add(b)),
+        }, a + b).ok_or_else(|| RuntimeErrorKind::Overflow("addition", self.type_name()))
+    }
+
+    pub fn subtract(self, rhs: Number, overflow: Overflow) -> Result<Number, RuntimeErrorKind> {
+        same_type!(self, rhs, |a, b| match overflow {
+            Overflow::Checked => a.checked_sub(b),
+            Overflow::Wrapping => Some(a.wrapping_sub(b)),
+        }, a - b).ok_or_else(|| RuntimeErrorKind::Overflow("subtraction", self.type_name()))
+    }
+
+    pub fn multiply(self, rhs: Number, overflow: Overflow) -> Result<Number, RuntimeErrorKind> {
+        same_type!(self, rhs, |a, b| match overflow {
+            Overflow::Checked => a.checked_mul(b),
+            Overflow::Wrapping => Some(a.wrapping_mul(b)),
+        }, a * b).ok_or_else(|| RuntimeErrorKind::Overflow("multiplication", self.type_name()))
+    }
+
+    /// integer division rounds towards zero, dividing an integer by zero is an error even when wrapping
+    pub fn divide(self, rhs: Number, overflow: Overflow) -> Result<Number, RuntimeErrorKind> {
+        if any_type!(rhs, |x| x == 0, |_x| false) {
+            return Err(RuntimeErrorKind::DivisionByZero)
+        }
+        same_type!(self, rhs, |a, b| match overflow {
+            Overflow::Checked => a.checked_div(b),
+            Overflow::Wrapping => Some(a.wrapping_div(b)),
+        }, a / b).ok_or_else(|| RuntimeErrorKind::Overflow("division", self.type_name()))
+    }
+
+    /// an integer can only be raised to a power that fits in a `u32`
+    pub fn raise_to(self, rhs: Number, overflow: Overflow) -> Result<Number, RuntimeErrorKind> {
+        if any_type!(rhs, |x| integer_try_from!(x, Integer::U32).is_none(), |_x| false) {
+            return Err(RuntimeErrorKind::InvalidConversion(rhs, "u32"))
+        }
+        same_type!(self, rhs, |a, b| match overflow {
+            Overflow::Checked => a.checked_pow(b as u32),
+            Overflow::Wrapping => Some(a.wrapping_pow(b as u32)),
+        }, a.powf(b)).ok_or_else(|| RuntimeErrorKind::Overflow("exponentiation", self.type_name()))
+    }
+
+    pub fn negate(self, overflow: Overflow) -> Result<Number, RuntimeErrorKind> {
+        // subtracting from zero is the same as negating for every type, and overflows the same way
+        let zero = match self {
+            Number::F32(x) => return Ok(Number::F32(-x)),
+            Number::F64(x) => return Ok(Number::F64(-x)),
+            integer => Number::from_integer(0, integer.integer_type(), Overflow::Checked).unwrap_or_else(|| ice!("zero fits in every integer type")),
+        };
+        zero.subtract(self, overflow).map_err(|_| RuntimeErrorKind::Overflow("negation", self.type_name()))
+    }
+
+    /// `None` when either of them is NaN
+    pub fn compare(self, rhs: Number) -> Result<Option<Ordering>, RuntimeErrorKind> {
+        if std::mem::discriminant(&self) != std::mem::discriminant(&rhs) {
+            return Err(mixed(self, rhs))
+        }
+        Ok(self.partial_cmp(&rhs))
+    }
+
+    /// what a literal of no particular type is as the same type as `other`. integers become any number,
+    /// floats only become other floats, mixing them with integers is still an error
+    pub fn like(self, other: Number) -> Result<Number, RuntimeErrorKind> {
+        match (self, other) {
+            // usually the literal already has the type, inference just couldn't tell
+            _ if std::mem::discriminant(&self) == std::mem::discriminant(&other) => Ok(self),
+            (_, Number::F32(_)) => Ok(self.to_float(Float::F32)),
+            (_, Number::F64(_)) => Ok(self.to_float(Float::F64)),
+            (Number::F32(_) | Number::F64(_), _) => Ok(self),
+            (_, integer) => self.to_integer(integer.integer_type(), Overflow::Checked),
+        }
+    }
+
+    fn integer_type(self) -> Integer {
+        match self {
+            Number::U8(_) => Integer::U8,
+            Number::U16(_) => Integer::U16,
+            Number::U32(_) => Integer::U32,
+            Number::U64(_) => Integer::U64,
+            Number::U128(_) => Integer::U128,
+            Number::I8(_) => Integer::I8,
+            Number::I16(_) => Integer::I16,
+            Number::I32(_) => Integer::I32,
+            Number::I64(_) => Integer::I64,
+            Number::I128(_) => Integer::I128,
+            Number::F32(_) | Number::F64(_) => ice!("not an integer"),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::U8(x) => write!(f, "{}", x),
+            Number::U16(x) => write!(f, "{}", x),
+            Number::U32(x) => write!(f, "{}", x),
+            Number::U64(x) => write!(f, "{}", x),
+            Number::U128(x) => write!(f, "{}", x),
+            Number::I8(x) => write!(f, "{}", x),
+            Number::I16(x) => write!(f, "{}", x),
+            Number::I32(x) => write!(f, "{}", x),
+            Number::I64(x) => write!(f, "{}", x),
+            Number::I128(x) => write!(f, "{}", x),
+            Number::F32(x) => write!(f, "{}", x),
+            Number::F64(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+/// like `EnumDef`, compared by identity
+#[derive(Debug)]
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+impl PartialEq for StructDef {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// struct values are shared between copies, but compared field by field
+#[derive(Debug, PartialEq)]
+pub struct Struct {
+    pub def: Rc<StructDef>,
+    pub fields: Vec<Value>,
+}
+
+/// compared by identity, as two declarations with the same shape are still different enums
+#[derive(Debug)]
+pub struct EnumDef {
+    pub name: String,
+    pub variants: Vec<VariantDef>,
+}
+
+impl PartialEq for EnumDef {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+#[derive(Debug)]
+pub struct VariantDef {
+    pub name: String,
+    pub fields: VariantFields,
+}
+
+#[derive(Debug)]
+pub enum VariantFields {
+    Unit,
+    Tuple(usize),
+    Struct(Vec<String>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Variant {
+    pub def: Rc<EnumDef>,
+    pub index: usize,
+    pub payload: Vec<Value>,
+}
+
+/// a function along with the scopes it was created in
+pub struct Closure {
+    pub function: usize,
+    /// outermost first
+    pub environment: Vec<Captured>,
+    /// the program `function` points into, which isn't always the one that calls it
+    pub program: Rc<Program>,
+}
+
+impl Closure {
+    /// the scopes the closure runs in
+    fn scopes(&self) -> Vec<Rc<RefCell<Scope>>> {
+        self.environment.iter().map(|captured| match captured {
+            Captured::Strong(scope) => scope.clone(),
+            Captured::Weak(scope) => scope.upgrade().unwrap_or_else(|| ice!("a closure outlived the scope it was stored in")),
+        }).collect()
+    }
+}
+
+// closures usually end up stored in one of the scopes they captured, so these can't look inside the environment
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<closure @ {} ({} scopes)>", self.function, self.environment.len())
+    }
+}
+
+// a closure read out of a scope is a copy of the one stored there, so this compares what they were made from
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        self.function == other.function
+            && Rc::ptr_eq(&self.program, &other.program)
+            && self.environment.len() == other.environment.len()
+            && self.environment.iter().zip(&other.environment).all(|(a, b)| std::ptr::eq(a.as_ptr(), b.as_ptr()))
+    }
+}
+
+/// one of the scopes a closure was created in. a closure stored in one of those scopes only holds that one weakly,
+/// otherwise they would keep each other alive forever
+#[derive(Debug, Clone)]
+pub enum Captured {
+    Strong(Rc<RefCell<Scope>>),
+    Weak(Weak<RefCell<Scope>>),
+}
+
+impl Captured {
+    fn as_ptr(&self) -> *const RefCell<Scope> {
+        match self {
+            Captured::Strong(scope) => Rc::as_ptr(scope),
+            Captured::Weak(scope) => scope.as_ptr(),
+        }
+    }
+}
+
+/// what goes into a slot of `scope` when `value` is stored there
+pub fn stored_in(value: Value, scope: &Rc<RefCell<Scope>>) -> Value {
+    match value {
+        Value::Closure(closure) if closure.environment.iter().any(|captured| matches!(captured, Captured::Strong(captured) if Rc::ptr_eq(captured, scope))) => {
+            let environment = closure.environment.iter().map(|captured| match captured {
+                Captured::Strong(captured) if Rc::ptr_eq(captured, scope) => Captured::Weak(Rc::downgrade(captured)),
+                captured => captured.clone(),
+            }).collect();
+            Value::Closure(Rc::new(Closure { function: closure.function, environment, program: closure.program.clone() }))
+        },
+        value => value,
+    }
+}
+
+/// undoes `stored_in` for a value read from a slot of `scope`, so it keeps `scope` alive wherever it ends up
+pub fn loaded_from(value: &Value, scope: &Rc<RefCell<Scope>>) -> Value {
+    match value {
+        Value::Closure(closure) if closure.environment.iter().any(|captured| matches!(captured, Captured::Weak(_)) && std::ptr::eq(captured.as_ptr(), Rc::as_ptr(scope))) => {
+            let environment = closure.environment.iter().map(|captured| match captured {
+                Captured::Weak(_) if std::ptr::eq(captured.as_ptr(), Rc::as_ptr(scope)) => Captured::Strong(scope.clone()),
+                captured => captured.clone(),
+            }).collect();
+            Value::Closure(Rc::new(Closure { function: closure.function, environment, program: closure.program.clone() }))
+        },
+        value => value.clone(),
+    }
+}
+
+/// gets the arguments in the order they were given
+pub type NativeFunction = dyn Fn(&[Value]) -> Result<Value, RuntimeError>;
+
+/// a function written in Rust
+pub struct NativeFn {
+    pub name: String,
+    pub function: Box<NativeFunction>,
+}
+
+impl NativeFn {
+    pub fn new<F: Fn(&[Value]) -> Result<Value, RuntimeError> + 'static>(name: &str, function: F) -> NativeFn {
+        NativeFn { name: name.to_owned(), function: Box::new(function) }
+    }
+}
+
+impl fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native function {}>", self.name)
+    }
+}
+
+impl PartialEq for NativeFn {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
 }
 
 #[derive(Debug, Default, PartialEq)]
 pub struct Scope {
-    pub stuff: HashMap<String, Value>,
+    /// what each slot was declared as, for property access on modules and for runtime lookups
+    pub names: Vec<String>,
+    pub slots: Vec<Value>,
+}
+
+impl Scope {
+    pub fn lookup(&self, name: &str) -> Option<usize> {
+        self.names.iter().rposition(|declared| declared == name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.lookup(name).map(|slot| &self.slots[slot])
+    }
+
+    pub fn declare(&mut self, slot: usize, name: &str, value: Value) {
+        if slot >= self.slots.len() {
+            self.names.resize(slot + 1, String::new());
+            self.slots.resize(slot + 1, Value::Nothing);
+        }
+        self.names[slot] = name.to_owned();
+        self.slots[slot] = value
+    }
+
+    /// overwrites the latest declaration of `name`, or declares it in a new slot
+    pub fn set(&mut self, name: &str, value: Value) {
+        let slot = self.lookup(name).unwrap_or(self.slots.len());
+        self.declare(slot, name, value)
+    }
 }
 
 #[derive(Debug)]
 pub struct VM {
     ip: usize,
     n: usize,
-    program: Program,
+    program: Rc<Program>,
     stack: Vec<Value>,
-    scopes: Vec<Scope>,
+    scopes: Vec<Rc<RefCell<Scope>>>,
+    /// one for each function that is still running, innermost last
+    frames: Vec<CallFrame>,
+    limits: Limits,
+    /// set when the program only calls a function for the host, so it's left out of traces
+    host_call: bool,
+    debug: DebugState,
+    profile: Option<Profile>,
+    coverage: Option<Coverage>,
+    overflow: Overflow,
+}
+
+/// which instructions ran, collected by `VM::enable_coverage`. only the program the VM was created with is covered
+#[derive(Debug)]
+pub struct Coverage {
+    program: Rc<Program>,
+    /// how often each instruction ran
+    pub hits: Vec<u64>,
+    /// (jumped, fell through) for each `ConditionalJump` that ran, by instruction.
+    /// `if`, `while` and `match` invert their condition first, so jumping means it was false
+    pub branches: HashMap<usize, (u64, u64)>,
+}
+
+impl Coverage {
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Cost {
+    pub instructions: u64,
+    pub time: Duration,
+}
+
+impl Cost {
+    fn add(&mut self, time: Duration) {
+        self.instructions += 1;
+        self.time += time
+    }
+}
+
+/// where the time went, collected by `VM::enable_profiling`. functions are told apart by their `FunctionTag`s,
+/// so a program compiled without them only has the top level
+#[derive(Debug)]
+pub struct Profile {
+    /// every call stack that ran something, as function names from the outermost one in
+    pub stacks: Vec<Vec<String>>,
+    /// what ran while each of `stacks` was the current one, not counting the functions it called
+    pub stack_costs: Vec<Cost>,
+    /// by the expression each instruction was generated from
+    pub spans: HashMap<Span, Cost>,
+    /// (parent, function) -> index in `stacks`
+    children: HashMap<(usize, String), usize>,
+    /// the index in `stacks` for each function that's running
+    current: Vec<usize>,
+}
+
+impl Profile {
+    fn new() -> Profile {
+        Profile { stacks: vec![vec!["<top level>".to_owned()]], stack_costs: vec![Cost::default()], spans: HashMap::new(), children: HashMap::new(), current: vec![0] }
+    }
+
+    fn enter(&mut self, function: &str) {
+        let parent = *self.current.last().unwrap_or(&0);
+        let next = self.stacks.len();
+        let stack = *self.children.entry((parent, function.to_owned())).or_insert(next);
+        if stack == next {
+            let mut names = self.stacks[parent].clone();
+            names.push(function.to_owned());
+            self.stacks.push(names);
+            self.stack_costs.push(Cost::default());
+        }
+        self.current.push(stack)
+    }
+
+    fn leave(&mut self) {
+        if self.current.len() > 1 {
+            self.current.pop();
+        }
+    }
+}
+
+/// how far to run before pausing again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugCommand {
+    /// until a breakpoint
+    Continue,
+    /// until any other line, including inside of a function that gets called
+    StepInto,
+    /// until another line of the same function, or until it returns
+    StepOver,
+    /// until the current function returns
+    StepOut,
+    /// not at all, `VM::debug` returns without finishing the program
+    Stop,
+}
+
+/// lets a front end pause the VM, look around and decide how far to go next
+pub trait Debugger {
+    /// the line `span` starts on. stepping goes line by line, and breakpoints are checked when a line is entered
+    fn line(&mut self, span: Span) -> usize;
+    fn has_breakpoint(&mut self, file: usize, line: usize) -> bool;
+    /// called before the instruction at `vm.ip()` runs, execution continues once this returns
+    fn paused(&mut self, vm: &mut VM) -> DebugCommand;
+    /// where `show` goes while debugging
+    fn show(&mut self, text: &str) {
+        println!("{}", text)
+    }
+}
+
+/// (file, line, call depth)
+type Location = (usize, usize, usize);
+
+#[derive(Debug)]
+struct DebugState {
+    command: DebugCommand,
+    /// how many functions were running when `command` was given
+    depth: usize,
+    /// where execution paused, until it moves on to another line of the same function or a caller.
+    /// coming back to it after a call doesn't count as entering it
+    from: Option<Location>,
+    /// the latest location an instruction ran at
+    last: Option<Location>,
+}
+
+/// what a program may use before `run` stops it, `None` means there's no limit
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// how many more instructions can be executed
+    pub fuel: Option<u64>,
+    /// how many functions can be running at once
+    pub max_call_depth: Option<usize>,
+    /// how many scopes can be open at once, including the ones outside of the program
+    pub max_scope_depth: Option<usize>,
+    /// a rough number of bytes for everything the program can still reach, only checked every `HEAP_CHECK_INTERVAL` instructions
+    pub max_heap: Option<usize>,
+}
+
+pub const HEAP_CHECK_INTERVAL: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Fuel,
+    CallDepth(usize),
+    ScopeDepth(usize),
+    Heap(usize),
+}
+
+#[derive(Debug)]
+struct CallFrame {
+    /// the `Call` instruction, execution resumes right after it
+    return_ip: usize,
+    /// where the arguments start on the stack; everything from here up belongs to the function
+    base: usize,
+    arguments: usize,
+    /// the caller's scopes and program, the function runs in the ones its closure captured
+    scopes: Vec<Rc<RefCell<Scope>>>,
+    program: Rc<Program>,
+}
+
+#[derive(Debug)]
+pub enum RuntimeErrorKind {
+    /// `expected` says what kind of value was needed, like "a number"
+    TypeMismatch { expected: &'static str, got: Value },
+    UndefinedVariable(String),
+    UndeclaredAssignment(String),
+    ArityMismatch { callee: String, expected: usize, got: usize },
+    NotCallable(Value),
+    NoSuchProperty { of: Value, name: String },
+    InvalidConstruction(String),
+    NoMatchingArm(Value),
+    Panic(Value),
+    /// whatever a native function wants to complain about
+    Native(String),
+    /// the instruction that hit the limit hasn't run yet, so `run` can pick up from there once the limits are raised
+    LimitExceeded(Limit),
+    /// numbers of different types, which have to be converted with `as` first
+    MixedNumbers(Number, Number),
+    /// (operation, type) for integer arithmetic that went out of range while overflow is checked
+    Overflow(&'static str, &'static str),
+    DivisionByZero,
+    /// (value, type it didn't fit in)
+    InvalidConversion(Number, &'static str),
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::TypeMismatch { expected, got } => write!(f, "expected {}, got {}", expected, quoted(got)),
+            RuntimeErrorKind::UndefinedVariable(name) => write!(f, "{} is not defined in this scope or any of its parents", name),
+            RuntimeErrorKind::UndeclaredAssignment(name) => write!(f, "cannot assign to {}, it hasn't been declared (use `let {} = ...` to declare it)", name, name),
+            RuntimeErrorKind::ArityMismatch { callee, expected, got } => write!(f, "{} requires {} arguments but received {}", callee, expected, got),
+            RuntimeErrorKind::NotCallable(value) => write!(f, "{} can't be called", quoted(value)),
+            RuntimeErrorKind::NoSuchProperty { of, name } => write!(f, "{} has no property {}", quoted(of), name),
+            RuntimeErrorKind::InvalidConstruction(message) => write!(f, "{}", message),
+            RuntimeErrorKind::NoMatchingArm(value) => write!(f, "no arm of this match matched {}", quoted(value)),
+            RuntimeErrorKind::Panic(value) => write!(f, "panicked with {}", quoted(value)),
+            RuntimeErrorKind::Native(message) => write!(f, "{}", message),
+            RuntimeErrorKind::LimitExceeded(Limit::Fuel) => write!(f, "ran out of fuel"),
+            RuntimeErrorKind::LimitExceeded(Limit::CallDepth(max)) => write!(f, "exceeded the maximum call depth of {}", max),
+            RuntimeErrorKind::LimitExceeded(Limit::ScopeDepth(max)) => write!(f, "exceeded the maximum scope depth of {}", max),
+            RuntimeErrorKind::LimitExceeded(Limit::Heap(max)) => write!(f, "used more than {} bytes of memory", max),
+            RuntimeErrorKind::MixedNumbers(lhs, rhs) => write!(f, "can't mix {} and {}, convert one of them with `as`", lhs.type_name(), rhs.type_name()),
+            RuntimeErrorKind::Overflow(operation, r#type) => write!(f, "{} overflowed {}", operation, r#type),
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::InvalidConversion(value, to) => write!(f, "{} {} doesn't fit in {}", value.type_name(), value, to),
+        }
+    }
+}
+
+/// a function that was running when an error happened
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// `None` for the top level, and for everything when the program has no `FunctionTag`s
+    pub function: Option<String>,
+    pub ip: usize,
+    pub span: Option<Span>,
+}
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    /// innermost call first
+    pub trace: Vec<Frame>,
+}
+
+/// for native functions, the VM fills in the trace
+impl From<RuntimeErrorKind> for RuntimeError {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        RuntimeError { kind, trace: vec![] }
+    }
+}
+
+impl RuntimeError {
+    pub fn to_error(&self) -> Error {
+        let mut message = self.kind.to_string();
+        let mut spans = vec![];
+        for (depth, frame) in self.trace.iter().enumerate() {
+            let function = frame.function.as_deref().unwrap_or("<top level>");
+            match frame.span {
+                Some(span) if depth == 0 => spans.push((format!("in {}", function), span)),
+                Some(span) => spans.push((format!("called from here, in {}", function), span)),
+                None => message.push_str(&format!("\n    in {} (at {})", function, frame.ip)),
+            }
+        }
+        spans.into_iter().fold(Error::new(message, codes::E0022.0, Severity::FatalError), |error, (label, span)| error.push(label, span))
+    }
 }
 
 impl VM {
     pub fn new(program: Program) -> VM {
-        VM { ip: 0, n: 0, program, stack: vec![], scopes: vec![Scope::default()] }
+        VM::with_scopes(program, vec![Rc::new(RefCell::new(Scope::default()))])
     }
 
-    pub fn run(&mut self) {
-        macro_rules! get {
-            () => {
-                self.stack.pop().unwrap_or_else(|| ice!("stack is empty :("))
-            };
+    /// the program's top level runs in the last scope, the others are only reachable by name
+    pub fn with_scopes(program: Program, scopes: Vec<Rc<RefCell<Scope>>>) -> VM {
+        VM { ip: 0, n: 0, program: Rc::new(program), stack: vec![], scopes, frames: vec![], limits: Limits::default(), host_call: false, debug: DebugState { command: DebugCommand::Continue, depth: 0, from: None, last: None }, profile: None, coverage: None, overflow: Overflow::default() }
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow
+    }
+
+    /// lets a program that ran out of fuel keep going on the next `run`
+    pub fn add_fuel(&mut self, fuel: u64) {
+        if let Some(remaining) = &mut self.limits.fuel {
+            *remaining += fuel
         }
+    }
 
-        macro_rules! get_num {
-            () => {
-                {
-                    let val = self.stack.pop().unwrap_or_else(|| ice!("stack is empty :("));
-                    if let Value::Num(x) = val {
-                        x
-                    } else {
-                        panic!("expected a number and got {:?} (at {})", val, self.ip)
-                    }
-                }
-            };
+    /// roughly how many bytes the values reachable from the stack and the scopes take up
+    fn heap_size(&self) -> usize {
+        let mut seen = HashSet::new();
+        let values = self.stack.iter().map(|value| value_size(value, &mut seen)).sum::<usize>();
+        let scopes = self.frames.iter().flat_map(|frame| frame.scopes.iter()).chain(self.scopes.iter()).collect::<Vec<_>>();
+        values + scopes.into_iter().map(|scope| scope_size(scope, &mut seen)).sum::<usize>()
+    }
+
+    /// sets up a call to a function (or constructor) from outside of any program, through one that only contains the call
+    pub fn with_call(function: Value, mut arguments: Vec<Value>) -> VM {
+        let how_many = arguments.len();
+        arguments.push(function);
+        let program = Program { instructions: vec![Instruction::Call(how_many), Instruction::Terminate], constants: vec![], names: vec![], spans: vec![None, None] };
+        let mut vm = VM::with_scopes(program, vec![]);
+        vm.stack = arguments;
+        vm.host_call = true;
+        vm
+    }
+
+    pub fn call(function: Value, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        VM::with_call(function, arguments).run()
+    }
+
+    fn error(&self, kind: RuntimeErrorKind) -> RuntimeError {
+        RuntimeError { kind, trace: self.trace() }
+    }
+
+    fn trace(&self) -> Vec<Frame> {
+        let innermost = (self.ip, &self.program);
+        let mut trace = std::iter::once(innermost).chain(self.frames.iter().rev().map(|frame| (frame.return_ip, &frame.program))).map(|(ip, program)| {
+            Frame { function: function_at(program, ip), ip, span: program.spans[ip] }
+        }).collect::<Vec<_>>();
+        if self.host_call {
+            trace.pop();
         }
+        trace
+    }
 
-        macro_rules! get_bool {
-            () => {
-                {
-                    let val = self.stack.pop().unwrap_or_else(|| ice!("stack is empty :("));
-                    if let Value::Bool(x) = val {
-                        x
-                    } else {
-                        panic!("expected a bool and got {:?}", val)
-                    }
-                }
-            };
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(Profile::new);
+    }
+
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    pub fn enable_coverage(&mut self) {
+        let program = self.program.clone();
+        self.coverage.get_or_insert_with(|| Coverage { hits: vec![0; program.instructions.len()], branches: HashMap::new(), program });
+    }
+
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
+
+    /// the scopes of the function `depth` calls out from the current one, innermost last.
+    /// `None` when there's no such function, `backtrace` has one frame for each `depth`
+    pub fn scopes(&self, depth: usize) -> Option<&[Rc<RefCell<Scope>>]> {
+        match depth {
+            0 => Some(&self.scopes),
+            _ => self.frames.len().checked_sub(depth).map(|frame| &self.frames[frame].scopes[..]),
         }
+    }
+
+    /// the functions that are running, innermost first
+    pub fn backtrace(&self) -> Vec<Frame> {
+        self.trace()
+    }
+
+    /// runs until the end of the program, and gives back the value its top level evaluated to
+    pub fn run(&mut self) -> Result<Value, RuntimeError> {
+        self.execute(None)
+    }
 
-        macro_rules! get_fn {
+    /// like `run`, but lets `debugger` pause execution, starting with `command` as if it had paused before the first instruction.
+    /// gives back `None` if the debugger stopped the program
+    pub fn debug(&mut self, debugger: &mut dyn Debugger, command: DebugCommand) -> Result<Option<Value>, RuntimeError> {
+        self.debug = DebugState { command, depth: self.frames.len(), from: None, last: None };
+        let value = self.execute(Some(debugger))?;
+        Ok(Some(value).filter(|_| self.debug.command != DebugCommand::Stop))
+    }
+
+    fn check_debugger(&mut self, debugger: &mut dyn Debugger) {
+        let Some(span) = self.program.spans[self.ip] else {
+            return
+        };
+        // the ones that span several lines belong to things like blocks and loops, the lines inside have instructions of their own
+        let line = debugger.line(span);
+        if debugger.line(Span::new(span.file, span.end as usize, span.end as usize)) != line {
+            return
+        }
+        let depth = self.frames.len();
+        let here = (span.file, line, depth);
+        let entered = self.debug.last != Some(here);
+        self.debug.last = Some(here);
+        if !entered || self.debug.from == Some(here) {
+            return
+        }
+        if self.debug.from.is_some_and(|(_, _, from)| depth <= from) {
+            self.debug.from = None
+        }
+        let stepped = match self.debug.command {
+            DebugCommand::Continue => false,
+            DebugCommand::StepInto => true,
+            DebugCommand::StepOver => depth <= self.debug.depth,
+            DebugCommand::StepOut => depth < self.debug.depth,
+            DebugCommand::Stop => false,
+        };
+        if stepped || debugger.has_breakpoint(here.0, here.1) {
+            self.debug.command = debugger.paused(self);
+            self.debug.depth = depth;
+            self.debug.from = Some(here);
+        }
+    }
+
+    fn execute(&mut self, mut debugger: Option<&mut dyn Debugger>) -> Result<Value, RuntimeError> {
+        macro_rules! fail {
+            ($kind:expr) => {{
+                let kind = $kind;
+                return Err(self.error(kind))
+            }};
+        }
+
+        macro_rules! get {
             () => {
-                {
-                    let val = self.stack.pop().unwrap_or_else(|| ice!("stack is empty :("));
-                    if let Value::Fn(x) = val {
-                        x
-                    } else {
-                        panic!("expected a function and got {:?}", val)
-                    }
-                }
+                self.stack.pop().unwrap_or_else(|| ice!("stack is empty :("))
             };
         }
 
-        macro_rules! get_scope {
+        macro_rules! get_num {
             () => {
                 {
                     let val = self.stack.pop().unwrap_or_else(|| ice!("stack is empty :("));
-                    if let Value::Scope(x) = val {
+                    if let Value::Num(x) = val {
                         x
                     } else {
-                        panic!("expected a scope and got {:?}", val)
+                        fail!(RuntimeErrorKind::TypeMismatch { expected: "a number", got: val })
                     }
                 }
             };
         }
 
-        macro_rules! get_jumpref {
+        macro_rules! get_bool {
             () => {
                 {
                     let val = self.stack.pop().unwrap_or_else(|| ice!("stack is empty :("));
-                    if let Value::JumpRef(x) = val {
+                    if let Value::Bool(x) = val {
                         x
                     } else {
-                        ice!("expected a JumpRef and got {:?} @ {}", val, self.ip)
+                        fail!(RuntimeErrorKind::TypeMismatch { expected: "a boolean", got: val })
                     }
                 }
             };
         }
 
-        macro_rules! identity {
-            ($e:expr) => { $e }
-        }
-
         macro_rules! op {
-            ($op:tt) => {{
+            ($op:ident) => {{
                 let rhs = get_num!();
                 let lhs = get_num!();
-                self.stack.push(Value::Num(identity!(lhs $op rhs)))
+                match lhs.$op(rhs, self.overflow) {
+                    Ok(val) => self.stack.push(Value::Num(val)),
+                    Err(kind) => fail!(kind),
+                }
             }};
         }
 
         macro_rules! op_num_comparison {
-            ($op:tt) => {{
+            ($($ordering:pat),+) => {{
                 let rhs = get_num!();
                 let lhs = get_num!();
-                self.stack.push(Value::Bool(identity!(lhs $op rhs)))
+                match lhs.compare(rhs) {
+                    Ok(ordering) => self.stack.push(Value::Bool(matches!(ordering, $(Some($ordering))|+))),
+                    Err(kind) => fail!(kind),
+                }
             }};
         }
 
+        // limits, the debugger, the profiler and coverage are usually all off. nothing else can turn them on while this runs,
+        // the debugger is the only thing that gets the machine, and it's checked for on every instruction anyway
+        let watched = debugger.is_some() || self.limits.fuel.is_some() || self.limits.max_heap.is_some() || self.profile.is_some() || self.coverage.is_some();
         while self.ip < self.program.instructions.len() {
+            let started = if watched {
+                if self.limits.fuel == Some(0) {
+                    fail!(RuntimeErrorKind::LimitExceeded(Limit::Fuel))
+                }
+                if let Some(max) = self.limits.max_heap {
+                    if self.n.is_multiple_of(HEAP_CHECK_INTERVAL) && self.heap_size() > max {
+                        fail!(RuntimeErrorKind::LimitExceeded(Limit::Heap(max)))
+                    }
+                }
+                if let Some(fuel) = &mut self.limits.fuel {
+                    *fuel -= 1
+                }
+                if let Some(debugger) = debugger.as_deref_mut() {
+                    self.check_debugger(debugger);
+                    if self.debug.command == DebugCommand::Stop {
+                        return Ok(Value::Nothing)
+                    }
+                }
+                if let Some(coverage) = self.coverage.as_mut().filter(|coverage| Rc::ptr_eq(&coverage.program, &self.program)) {
+                    coverage.hits[self.ip] += 1
+                }
+                // calls and returns change the current stack, the cost belongs to the one the instruction started in
+                self.profile.as_ref().map(|profile| (Instant::now(), self.program.spans[self.ip], profile.current.last().copied().unwrap_or(0)))
+            } else {
+                None
+            };
             #[cfg(feature = "debug_execution")]
             println!("{} -- {:?} with {:?}", self.ip, self.program.instructions[self.ip], self.stack);
             match self.program.instructions[self.ip] {
@@ -201,9 +1086,6 @@ impl VM {
                     self.stack.push(v.clone());
                     self.stack.push(v)
                 }
-                Instruction::PushJumpRef(n) => {
-                    self.stack.push(Value::JumpRef(n))
-                }
                 Instruction::Swap => {
                     let a = get!();
                     let b = get!();
@@ -215,6 +1097,10 @@ impl VM {
                     let value = self.stack.pop();
                     match value {
                         Some(Value::Bool(x)) => {
+                            if let Some(coverage) = self.coverage.as_mut().filter(|coverage| Rc::ptr_eq(&coverage.program, &self.program)) {
+                                let (jumped, fell_through) = coverage.branches.entry(self.ip).or_default();
+                                *(if x { jumped } else { fell_through }) += 1
+                            }
                             if x {
                                 self.ip = to
                             }
@@ -222,137 +1108,414 @@ impl VM {
                         _ => ice!("stack is empty or non-boolean is on the stack (at {}) (stack = {:#?}) (value = {:#?})", self.ip, self.stack, value),
                     }
                 },
-                Instruction::Terminate => return,
+                Instruction::Terminate => break,
                 Instruction::RequireArguments(n) => {
-                    let how_many = self.stack.iter().rev().position(|x| matches!(x, Value::JumpRef(_))).unwrap_or_else(|| ice!("there should be a JumpRef here"));
+                    let how_many = self.frames.last().unwrap_or_else(|| ice!("arguments are only required inside of a function")).arguments;
                     if how_many != n {
-                        panic!("function requires {n} arguments but received {how_many}")
+                        let callee = function_at(&self.program, self.ip).unwrap_or_else(|| "function".to_owned());
+                        fail!(RuntimeErrorKind::ArityMismatch { callee, expected: n, got: how_many })
                     }
                 },
                 Instruction::Null => (),
-                Instruction::Call => {
-                    let target = get_fn!();
-                    // self.stack.push(Value::JumpRef(self.ip + 1));
-                    self.ip = target
+                Instruction::Call(how_many) => {
+                    match get!() {
+                        Value::Closure(closure) => {
+                            if let Some(max) = self.limits.max_call_depth.filter(|max| self.frames.len() >= *max) {
+                                // the callee has to be back on the stack to resume
+                                self.stack.push(Value::Closure(closure));
+                                fail!(RuntimeErrorKind::LimitExceeded(Limit::CallDepth(max)))
+                            }
+                            let callers_scopes = std::mem::replace(&mut self.scopes, closure.scopes());
+                            let callers_program = std::mem::replace(&mut self.program, closure.program.clone());
+                            self.frames.push(CallFrame { return_ip: self.ip, base: self.stack.len() - how_many, arguments: how_many, scopes: callers_scopes, program: callers_program });
+                            self.ip = closure.function
+                        },
+                        Value::Native(native) => {
+                            let arguments = self.stack.split_off(self.stack.len() - how_many);
+                            match (native.function)(&arguments) {
+                                Ok(value) => self.stack.push(value),
+                                Err(mut error) => {
+                                    // anything already in the trace happened inside of the native function
+                                    error.trace.extend(self.trace());
+                                    return Err(error)
+                                },
+                            }
+                        },
+                        Value::Constructor(def, index) => {
+                            let variant = &def.variants[index];
+                            match variant.fields {
+                                VariantFields::Tuple(n) if n == how_many => (),
+                                VariantFields::Tuple(n) => fail!(RuntimeErrorKind::ArityMismatch { callee: format!("{}.{}", def.name, variant.name), expected: n, got: how_many }),
+                                _ => fail!(RuntimeErrorKind::InvalidConstruction(format!("{}.{} has named fields, use {}.{} {{ ... }} to construct it", def.name, variant.name, def.name, variant.name))),
+                            }
+                            let payload = self.stack.split_off(self.stack.len() - how_many);
+                            self.stack.push(Value::Variant(Rc::new(Variant { def, index, payload })))
+                        },
+                        val => fail!(RuntimeErrorKind::NotCallable(val)),
+                    }
+                },
+                Instruction::MakeClosure => {
+                    let val = get!();
+                    if let Value::Fn(function) = val {
+                        self.stack.push(Value::Closure(Rc::new(Closure { function, environment: self.scopes.iter().cloned().map(Captured::Strong).collect(), program: self.program.clone() })))
+                    } else {
+                        ice!("expected a function prototype and got {:?}", val)
+                    }
                 },
                 Instruction::Panic => {
-                    panic!("\x1B[31merror: {:?}\x1B[0m", get!())
+                    fail!(RuntimeErrorKind::Panic(get!()))
+                },
+                Instruction::MatchFailed => {
+                    fail!(RuntimeErrorKind::NoMatchingArm(get!()))
+                },
+                Instruction::IsVariant => {
+                    let (def, index) = match get!() {
+                        Value::Constructor(def, index) => (def, index),
+                        Value::Variant(variant) if variant.payload.is_empty() => (variant.def.clone(), variant.index),
+                        val => fail!(RuntimeErrorKind::TypeMismatch { expected: "an enum variant", got: val }),
+                    };
+                    let matches = matches!(get!(), Value::Variant(variant) if Rc::ptr_eq(&variant.def, &def) && variant.index == index);
+                    self.stack.push(Value::Bool(matches))
                 },
                 Instruction::Negate => {
-                    let val = get_num!();
-                    self.stack.push(Value::Num(-val))
+                    match get_num!().negate(self.overflow) {
+                        Ok(val) => self.stack.push(Value::Num(val)),
+                        Err(kind) => fail!(kind),
+                    }
                 },
-                Instruction::Add => op!(+),
-                Instruction::Subtract => op!(-),
-                Instruction::Multiply => op!(*),
-                Instruction::Divide => op!(/),
+                Instruction::Add => op!(add),
+                Instruction::Subtract => op!(subtract),
+                Instruction::Multiply => op!(multiply),
+                Instruction::Divide => op!(divide),
                 Instruction::Invert => {
                     let val = get_bool!();
                     self.stack.push(Value::Bool(!val))
                 },
-                Instruction::RaiseTo => {
-                    let rhs = get_num!();
-                    let lhs = get_num!();
-                    self.stack.push(Value::Num(lhs.powf(rhs)))
+                Instruction::RaiseTo => op!(raise_to),
+                Instruction::ConvertToInteger(to) => {
+                    match get_num!().to_integer(to, self.overflow) {
+                        Ok(val) => self.stack.push(Value::Num(val)),
+                        Err(kind) => fail!(kind),
+                    }
+                },
+                Instruction::ConvertToFloat(to) => {
+                    let val = get_num!().to_float(to);
+                    self.stack.push(Value::Num(val))
+                },
+                Instruction::ConvertLiteral(depth) => {
+                    let top = self.stack.len().checked_sub(2).unwrap_or_else(|| ice!("stack is empty :(")) + 1;
+                    let (literal, other) = (top - depth, top + depth - 1);
+                    if let (Value::Num(number), Value::Num(like)) = (&self.stack[literal], &self.stack[other]) {
+                        match number.like(*like) {
+                            Ok(number) => self.stack[literal] = Value::Num(number),
+                            Err(kind) => fail!(kind),
+                        }
+                    }
                 },
                 Instruction::CheckEquality => {
-                    let val = get!() == get!();
-                    self.stack.push(Value::Bool(val))
+                    let rhs = get!();
+                    match equal(&get!(), &rhs) {
+                        Ok(val) => self.stack.push(Value::Bool(val)),
+                        Err(kind) => fail!(kind),
+                    }
                 },
-                Instruction::Lesser => op_num_comparison!(<),
-                Instruction::Greater => op_num_comparison!(>),
-                Instruction::LesserEq => op_num_comparison!(<=),
-                Instruction::GreaterEq => op_num_comparison!(>=),
+                Instruction::Lesser => op_num_comparison!(Ordering::Less),
+                Instruction::Greater => op_num_comparison!(Ordering::Greater),
+                Instruction::LesserEq => op_num_comparison!(Ordering::Less, Ordering::Equal),
+                Instruction::GreaterEq => op_num_comparison!(Ordering::Greater, Ordering::Equal),
                 Instruction::CheckInequality => {
-                    let val = get!() != get!();
-                    self.stack.push(Value::Bool(val))
+                    let rhs = get!();
+                    match equal(&get!(), &rhs) {
+                        Ok(val) => self.stack.push(Value::Bool(!val)),
+                        Err(kind) => fail!(kind),
+                    }
                 },
                 Instruction::Show => {
                     // eprintln!("after {}", self.n);
                     let val = get!();
-                    let s: String;
-                    println!("{}", match &val {
-                        Value::Fn(n) => {
-                            s = format!("<function @ {}>", n);
-                            &s
+                    match debugger.as_deref_mut() {
+                        Some(debugger) => debugger.show(&display(&val)),
+                        None => println!("{}", display(&val)),
+                    }
+                    self.stack.push(val)
+                },
+                Instruction::AccessProperty(property) => {
+                    let name = &self.program.names[property];
+                    let object = get!();
+                    let value = match &object {
+                        Value::Scope(scope) => scope.borrow().get(name).map(|value| loaded_from(value, scope)),
+                        Value::Enum(def) => def.variants.iter().position(|variant| &variant.name == name).map(|index| {
+                            if let VariantFields::Unit = def.variants[index].fields {
+                                Value::Variant(Rc::new(Variant { def: def.clone(), index, payload: vec![] }))
+                            } else {
+                                Value::Constructor(def.clone(), index)
+                            }
+                        }),
+                        Value::Struct(instance) => {
+                            let instance = instance.borrow();
+                            instance.def.fields.iter().position(|field| field == name).map(|position| instance.fields[position].clone())
                         },
-                        Value::Nothing => "<nothing>",
-                        Value::Num(n) => {
-                            s = n.to_string();
-                            &s
+                        Value::Variant(variant) => match &variant.def.variants[variant.index].fields {
+                            VariantFields::Struct(fields) => fields.iter().position(|field| field == name).map(|position| variant.payload[position].clone()),
+                            _ => None,
                         },
-                        Value::Bool(v) => if *v { "true" } else { "false" }
-                        Value::Str(s) => s,
-                        Value::JumpRef(n) => {
-                            s = format!("<jump ref : {}>", n);
-                            &s
+                        _ => fail!(RuntimeErrorKind::TypeMismatch { expected: "something with properties", got: object }),
+                    };
+                    match value {
+                        Some(value) => self.stack.push(value),
+                        None => fail!(RuntimeErrorKind::NoSuchProperty { of: object, name: name.clone() }),
+                    }
+                },
+                Instruction::Construct(how_many) => {
+                    let of = get!();
+                    let given = self.stack.split_off(self.stack.len() - how_many * 2);
+                    match of {
+                        Value::Constructor(def, index) => {
+                            let variant = &def.variants[index];
+                            let payload = if let VariantFields::Struct(ref fields) = variant.fields {
+                                arrange_fields(&format!("{}.{}", def.name, variant.name), fields, given)
+                            } else {
+                                Err(RuntimeErrorKind::InvalidConstruction(format!("{}.{} doesn't have named fields", def.name, variant.name)))
+                            };
+                            let payload = match payload {
+                                Ok(payload) => payload,
+                                Err(kind) => fail!(kind),
+                            };
+                            self.stack.push(Value::Variant(Rc::new(Variant { def, index, payload })))
                         },
-                        Value::Scope(scope) => {
-                            s = format!("<scope {:?}>", scope);
-                            &s
-                        }
-                    });
-                    self.stack.push(val)
+                        Value::StructDef(def) => {
+                            let fields = match arrange_fields(&def.name, &def.fields, given) {
+                                Ok(fields) => fields,
+                                Err(kind) => fail!(kind),
+                            };
+                            self.stack.push(Value::Struct(Rc::new(RefCell::new(Struct { def, fields }))))
+                        },
+                        val => fail!(RuntimeErrorKind::TypeMismatch { expected: "a struct or an enum variant with named fields", got: val }),
+                    }
                 },
-                Instruction::AccessProperty(property) => {
-                    let name = self.program.names[property].clone();
-                    let scope = get_scope!();
-                    let borrow = scope.borrow();
-                    self.stack.push(borrow.stuff.get(&name).expect(&name).clone())
+                Instruction::StoreProperty(property) => {
+                    let name = &self.program.names[property];
+                    let value = get!();
+                    let object = get!();
+                    let stored = match &object {
+                        Value::Struct(instance) => {
+                            let mut instance = instance.borrow_mut();
+                            let position = instance.def.fields.iter().position(|field| field == name);
+                            position.map(|position| instance.fields[position] = value).is_some()
+                        },
+                        Value::Scope(scope) => {
+                            let mut borrow = scope.borrow_mut();
+                            let slot = borrow.lookup(name);
+                            slot.map(|slot| borrow.slots[slot] = stored_in(value, scope)).is_some()
+                        },
+                        _ => fail!(RuntimeErrorKind::TypeMismatch { expected: "a struct or a module", got: object }),
+                    };
+                    if !stored {
+                        fail!(RuntimeErrorKind::NoSuchProperty { of: object, name: name.clone() })
+                    }
                 },
-                Instruction::NewScope => self.scopes.push(Scope::default()),
-                Instruction::LoadVar(index) => {
-                    let name = self.program.names[index].clone();
-                    let mut found = false;
-                    for i in (0..self.scopes.len()).rev() {
-                        if self.scopes[i].stuff.contains_key(&name) {
-                            self.stack.push(self.scopes[i].stuff.get(&name).unwrap().clone());
-                            found = true;
-                            break
-                        }
+                Instruction::PayloadItem(index) => {
+                    match get!() {
+                        Value::Variant(variant) => self.stack.push(variant.payload[index].clone()),
+                        val => ice!("expected an enum variant and got {:?}", val),
                     }
-                    if !found {
-                        panic!("{} is not defined in this scope or any of its parents", name)
+                },
+                Instruction::NewScope => {
+                    if let Some(max) = self.limits.max_scope_depth.filter(|max| self.scopes.len() >= *max) {
+                        fail!(RuntimeErrorKind::LimitExceeded(Limit::ScopeDepth(max)))
                     }
+                    self.scopes.push(Rc::new(RefCell::new(Scope::default())))
                 },
-                Instruction::AssignStore(index) => {
-                    let idx = self.scopes.len() - 1;
-                    let _ = self.scopes[idx].stuff.insert(self.program.names[index].clone(), get!());
+                Instruction::LoadLocal(depth, slot) => {
+                    let scope = &self.scopes[self.scopes.len() - 1 - depth];
+                    let value = loaded_from(scope.borrow().slots.get(slot).unwrap_or_else(|| ice!("slot {} of scope {} hasn't been declared", slot, depth)), scope);
+                    self.stack.push(value)
+                },
+                Instruction::StoreLocal(depth, slot) => {
+                    let value = get!();
+                    let scope = &self.scopes[self.scopes.len() - 1 - depth];
+                    *scope.borrow_mut().slots.get_mut(slot).unwrap_or_else(|| ice!("slot {} of scope {} hasn't been declared", slot, depth)) = stored_in(value, scope)
+                },
+                Instruction::DeclareLocal(slot, index) => {
+                    let value = get!();
+                    let scope = self.scopes.last().unwrap_or_else(|| ice!("expected a scope"));
+                    scope.borrow_mut().declare(slot, &self.program.names[index], stored_in(value, scope))
+                },
+                Instruction::LoadVar(index) => {
+                    let name = &self.program.names[index];
+                    let value = self.scopes.iter().rev().find_map(|scope| scope.borrow().get(name).map(|value| loaded_from(value, scope)));
+                    match value {
+                        Some(value) => self.stack.push(value),
+                        None => fail!(RuntimeErrorKind::UndefinedVariable(name.clone())),
+                    }
                 },
                 Instruction::Store(index) => {
-                    let name = self.program.names[index].clone();
-                    // let mut found = false;
-                    for i in (0..self.scopes.len()).rev() {
-                        if self.scopes[i].stuff.contains_key(&name) {
-                            self.scopes[i].stuff.insert(name, get!());
-                            // found = true;
-                            break
-                        }
+                    let name = &self.program.names[index];
+                    let value = get!();
+                    let scope = self.scopes.iter().rev().find(|scope| scope.borrow().lookup(name).is_some());
+                    match scope {
+                        Some(scope) => {
+                            let mut borrow = scope.borrow_mut();
+                            let slot = borrow.lookup(name).unwrap_or_else(|| ice!("the name was just found"));
+                            borrow.slots[slot] = stored_in(value, scope)
+                        },
+                        None => fail!(RuntimeErrorKind::UndeclaredAssignment(name.clone())),
                     }
                 },
                 Instruction::EndScope => {
                     let _ = self.scopes.pop();
                 },
-                Instruction::EndAndNameScope(index) => {
+                Instruction::EndModuleScope => {
                     let scope = self.scopes.pop().unwrap_or_else(|| ice!("expected a scope"));
-                    let idx = self.scopes.len() - 1;
-                    let scope = Rc::new(RefCell::new(scope));
-                    let _ = self.scopes[idx].stuff.insert(self.program.names[index].clone(), Value::Scope(scope));
+                    self.stack.push(Value::Scope(scope))
                 },
-                Instruction::PopJump => {
-                    let to = get_jumpref!();
-                    self.ip = to
+                Instruction::Return => {
+                    let frame = self.frames.pop().unwrap_or_else(|| ice!("returning from outside of a function"));
+                    let value = get!();
+                    self.stack.truncate(frame.base);
+                    self.stack.push(value);
+                    // this also gets rid of the function's own scopes
+                    self.scopes = frame.scopes;
+                    self.program = frame.program;
+                    self.ip = frame.return_ip;
+                    if let Some(profile) = &mut self.profile {
+                        profile.leave()
+                    }
                 },
                 Instruction::JumpTo(_) => ice!("this should have been turned into Jump"),
                 Instruction::ConditionalJumpTo(_) => ice!("this should have been turned into ConditionalJump"),
                 Instruction::JumpTarget(_) => ice!("this should have been turned into Null"),
-                Instruction::JumpRefTo(_) => ice!("this should have been turned into PushJumpRef"),
-                Instruction::FunctionTag(_) => (),
-                Instruction::CodegenHelper(_) => (),
+                Instruction::FunctionTag(name) => {
+                    if let Some(profile) = &mut self.profile {
+                        profile.enter(&self.program.names[name])
+                    }
+                },
+            }
+            if let (Some(profile), Some((start, span, stack))) = (&mut self.profile, started) {
+                let time = start.elapsed();
+                profile.stack_costs[stack].add(time);
+                if let Some(span) = span {
+                    profile.spans.entry(span).or_default().add(time)
+                }
             }
             self.ip += 1;
             self.n += 1
         }
+        Ok(self.stack.pop().unwrap_or(Value::Nothing))
+    }
+}
+
+/// `seen` makes sure shared values are only counted once, and that cycles through closures end
+fn value_size(value: &Value, seen: &mut HashSet<*const ()>) -> usize {
+    let inner = match value {
+        Value::Str(string) => string.capacity(),
+        Value::Closure(closure) if seen.insert(Rc::as_ptr(closure) as *const ()) => {
+            std::mem::size_of::<Closure>() + closure.scopes().iter().map(|scope| scope_size(scope, seen)).sum::<usize>()
+        },
+        Value::Scope(scope) => scope_size(scope, seen),
+        Value::Variant(variant) if seen.insert(Rc::as_ptr(variant) as *const ()) => {
+            std::mem::size_of::<Variant>() + variant.payload.iter().map(|value| value_size(value, seen)).sum::<usize>()
+        },
+        Value::Struct(instance) if seen.insert(Rc::as_ptr(instance) as *const ()) => {
+            std::mem::size_of::<Struct>() + instance.borrow().fields.iter().map(|value| value_size(value, seen)).sum::<usize>()
+        },
+        _ => 0,
+    };
+    std::mem::size_of::<Value>() + inner
+}
+
+fn scope_size(scope: &Rc<RefCell<Scope>>, seen: &mut HashSet<*const ()>) -> usize {
+    if !seen.insert(Rc::as_ptr(scope) as *const ()) {
+        return 0
+    }
+    let scope = scope.borrow();
+    let names = scope.names.iter().map(|name| std::mem::size_of::<String>() + name.capacity()).sum::<usize>();
+    std::mem::size_of::<Scope>() + names + scope.slots.iter().map(|value| value_size(value, seen)).sum::<usize>()
+}
+
+/// the function `ip` is in, found through the `FunctionTag` at the start of its body
+fn function_at(program: &Program, ip: usize) -> Option<String> {
+    program.instructions[..=ip].iter().rev().find_map(|instruction| match instruction {
+        Instruction::FunctionTag(name) => Some(program.names[*name].clone()),
+        _ => None,
+    })
+}
+
+/// puts the `(name, value)` pairs a literal gave in the order `field_names` declares them
+fn arrange_fields(owner: &str, field_names: &[String], mut given: Vec<Value>) -> Result<Vec<Value>, RuntimeErrorKind> {
+    for (index, pair) in given.chunks(2).enumerate() {
+        if given.chunks(2).take(index).any(|earlier| earlier[0] == pair[0]) {
+            return Err(RuntimeErrorKind::InvalidConstruction(format!("field {} of {} is given more than once", display(&pair[0]), owner)))
+        }
+    }
+    let mut fields = Vec::with_capacity(field_names.len());
+    for field in field_names {
+        let position = given.chunks(2).position(|pair| matches!(&pair[0], Value::Str(name) if name == field));
+        let position = position.ok_or_else(|| RuntimeErrorKind::InvalidConstruction(format!("missing field {} in {}", field, owner)))?;
+        fields.push(std::mem::replace(&mut given[position * 2 + 1], Value::Nothing));
+        given[position * 2] = Value::Nothing;
+    }
+    if let Some(pair) = given.chunks(2).find(|pair| pair[0] != Value::Nothing) {
+        return Err(RuntimeErrorKind::InvalidConstruction(format!("{} has no field {}", owner, display(&pair[0]))))
+    }
+    Ok(fields)
+}
+
+/// `==` for values, except that numbers of different types can't be compared, even inside variants and structs
+pub fn equal(lhs: &Value, rhs: &Value) -> Result<bool, RuntimeErrorKind> {
+    fn all_equal(lhs: &[Value], rhs: &[Value]) -> Result<bool, RuntimeErrorKind> {
+        for (lhs, rhs) in lhs.iter().zip(rhs) {
+            if !equal(lhs, rhs)? {
+                return Ok(false)
+            }
+        }
+        Ok(lhs.len() == rhs.len())
+    }
+
+    match (lhs, rhs) {
+        (Value::Num(lhs), Value::Num(rhs)) => Ok(lhs.compare(*rhs)? == Some(Ordering::Equal)),
+        (Value::Variant(lhs), Value::Variant(rhs)) => Ok(lhs.def == rhs.def && lhs.index == rhs.index && all_equal(&lhs.payload, &rhs.payload)?),
+        (Value::Struct(lhs), Value::Struct(rhs)) => {
+            let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+            Ok(lhs.def == rhs.def && all_equal(&lhs.fields, &rhs.fields)?)
+        },
+        (lhs, rhs) => Ok(lhs == rhs),
+    }
+}
+
+/// like `display`, but makes strings stand out from the rest of an error message
+fn quoted(value: &Value) -> String {
+    match value {
+        Value::Str(s) => format!("'{}'", s),
+        other => display(other),
+    }
+}
+
+pub fn display(value: &Value) -> String {
+    match value {
+        Value::Fn(n) => format!("<function @ {}>", n),
+        Value::Closure(closure) => format!("<function @ {}>", closure.function),
+        Value::Native(native) => format!("<native function {}>", native.name),
+        Value::Nothing => "<nothing>".to_owned(),
+        Value::Num(n) => n.to_string(),
+        Value::Bool(v) => (if *v { "true" } else { "false" }).to_owned(),
+        Value::Str(s) => s.clone(),
+        Value::Scope(scope) => format!("<scope {:?}>", scope),
+        Value::Enum(def) => format!("<enum {}>", def.name),
+        Value::Constructor(def, index) => format!("<constructor {}.{}>", def.name, def.variants[*index].name),
+        Value::StructDef(def) => format!("<struct {}>", def.name),
+        Value::Struct(instance) => {
+            let instance = instance.borrow();
+            format!("{} {{ {} }}", instance.def.name, instance.def.fields.iter().zip(instance.fields.iter()).map(|(name, value)| format!("{}: {}", name, display(value))).collect::<Vec<_>>().join(", "))
+        },
+        Value::Variant(variant) => {
+            let def = &variant.def.variants[variant.index];
+            match &def.fields {
+                VariantFields::Unit => format!("{}.{}", variant.def.name, def.name),
+                VariantFields::Tuple(_) => format!("{}.{}({})", variant.def.name, def.name, variant.payload.iter().map(display).collect::<Vec<_>>().join(", ")),
+                VariantFields::Struct(fields) => format!("{}.{} {{ {} }}", variant.def.name, def.name, fields.iter().zip(variant.payload.iter()).map(|(name, value)| format!("{}: {}", name, display(value))).collect::<Vec<_>>().join(", ")),
+            }
+        },
     }
 }
 
@@ -367,9 +1530,10 @@ pub fn show_program(program: &Program) {
             Value::Str(text) => format!("'{text}'"),
             Value::Bool(x) => (if *x { "true" } else { "false" }).to_owned(),
             Value::Fn(n) => format!("<function @ {n}>"),
+            Value::Closure(closure) => format!("<function @ {}>", closure.function),
             Value::Nothing => "<nothing>".to_owned(),
-            Value::JumpRef(n) => format!("<jump ref : {n}>"),
             Value::Scope(scope) => format!("<scope {:?}>", scope),
+            other => display(other),
         });
     }
     eprintln!("\n\x1B[32mNames:\x1B[0m");
@@ -390,22 +1554,25 @@ pub fn show_program(program: &Program) {
                 Value::Str(text) => format!("'{text}'"),
                 Value::Bool(x) => (if *x { "true" } else { "false" }).to_owned(),
                 Value::Fn(x) => format!("<function @ {x}>"),
+                Value::Closure(closure) => format!("<function @ {}>", closure.function),
                 Value::Nothing => "<nothing>".to_owned(),
-                Value::JumpRef(n) => format!("<jump ref : {n}>"),
                 Value::Scope(scope) => format!("<scope {:?}>", scope),
+                other => display(other),
             }, n),
             Instruction::Discard => "discard".to_owned(),
             Instruction::PushNothing => "push-nothing".to_owned(),
             Instruction::Duplicate => "duplicate".to_owned(),
-            Instruction::PushJumpRef(x) => format!("push-jump-ref\x1B[0m {}", x),
             Instruction::Swap => "swap".to_owned(),
             Instruction::Jump(to) => format!("jump\x1B[0m {}", to),
             Instruction::ConditionalJump(to) => format!("conditional-jump\x1B[0m {}", to),
             Instruction::Terminate => "---".to_owned(),
             Instruction::RequireArguments(n) => format!("require-args\x1B[0m {n}"),
             Instruction::Null => "".to_owned(),
-            Instruction::Call => "call".to_owned(),
+            Instruction::Call(n) => format!("call\x1B[0m {n}"),
             Instruction::Panic => "panic".to_owned(),
+            Instruction::MakeClosure => "make-closure".to_owned(),
+            Instruction::MatchFailed => "match-failed".to_owned(),
+            Instruction::IsVariant => "is-variant".to_owned(),
             Instruction::Negate => "neg".to_owned(),
             Instruction::Add => "add".to_owned(),
             Instruction::Subtract => "sub".to_owned(),
@@ -413,6 +1580,9 @@ pub fn show_program(program: &Program) {
             Instruction::Divide => "div".to_owned(),
             Instruction::Invert => "invert".to_owned(),
             Instruction::RaiseTo => "exp".to_owned(),
+            Instruction::ConvertToInteger(to) => format!("to-integer\x1B[0m {}", format!("{:?}", to).to_lowercase()),
+            Instruction::ConvertToFloat(to) => format!("to-float\x1B[0m {}", format!("{:?}", to).to_lowercase()),
+            Instruction::ConvertLiteral(depth) => format!("convert-literal\x1B[0m {}", depth),
             Instruction::CheckEquality => "check-eq".to_owned(),
             Instruction::Lesser => "lt".to_owned(),
             Instruction::Greater => "gt".to_owned(),
@@ -421,19 +1591,22 @@ pub fn show_program(program: &Program) {
             Instruction::CheckInequality => "check-neq".to_owned(),
             Instruction::Show => "show".to_owned(),
             Instruction::AccessProperty(v) => format!("read-property\x1B[0m {} \x1B[37m({})", &program.names[*v], v),
+            Instruction::Construct(n) => format!("construct\x1B[0m {n}"),
+            Instruction::StoreProperty(v) => format!("store-property\x1B[0m {} \x1B[37m({})", &program.names[*v], v),
+            Instruction::PayloadItem(n) => format!("payload-item\x1B[0m {n}"),
             Instruction::NewScope => "new-scope".to_owned(),
             Instruction::LoadVar(v) => format!("load-name\x1B[0m {} \x1B[37m({})", &program.names[*v], v),
             Instruction::Store(v) => format!("store\x1B[0m {} \x1B[37m({})", &program.names[*v], v),
-            Instruction::AssignStore(v) => format!("declare-store\x1B[0m {} \x1B[37m({})", &program.names[*v], v),
+            Instruction::LoadLocal(depth, slot) => format!("load-local\x1B[0m {}:{}", depth, slot),
+            Instruction::StoreLocal(depth, slot) => format!("store-local\x1B[0m {}:{}", depth, slot),
+            Instruction::DeclareLocal(slot, v) => format!("declare-local\x1B[0m {} {} \x1B[37m({})", slot, &program.names[*v], v),
             Instruction::EndScope => "end-scope".to_owned(),
-            Instruction::EndAndNameScope(v) => format!("end-store-scope\x1B[0m {} \x1B[37m({})", &program.names[*v], v),
-            Instruction::PopJump => "pop-jump".to_owned(),
+            Instruction::EndModuleScope => "end-module-scope".to_owned(),
+            Instruction::Return => "return".to_owned(),
             Instruction::JumpTo(_) => todo!(),
             Instruction::ConditionalJumpTo(_) => todo!(),
             Instruction::JumpTarget(_) => todo!(),
-            Instruction::JumpRefTo(_) => todo!(),
             Instruction::FunctionTag(tag) => format!("\x1B[35m{}:", &program.names[*tag]),
-            Instruction::CodegenHelper(tag) => format!("\x1B[35m-- {}", &program.names[*tag]),
         });
     }
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use eeeee::{engine::EngineError, vm, Engine};
+
+fn eval(src: &str) -> String {
+    match Engine::new().eval(src, "test") {
+        Ok(value) => vm::display(&value),
+        Err(error) => panic!("{}: {:?}", src, error),
+    }
+}
+
+/// the errors `src` was rejected with before running
+fn rejected(src: &str) -> String {
+    match Engine::new().eval(src, "test") {
+        Err(EngineError::Diagnostics(errors)) => format!("{:?}", errors),
+        other => panic!("{}: expected it to be rejected, got {:?}", src, other),
+    }
+}
+
+#[test]
+fn annotated_code_runs_like_code_without_annotations() {
+    assert_eq!(eval("let x: f64 = 1.5; let name: str = 'a'; fn twice(s: str, n: i32) -> i32 { n * 2 }; twice(name, 21)"), "42");
+    assert_eq!(eval("let anything: _ = 'inferred'; anything"), "inferred");
+}
+
+#[test]
+fn annotations_constrain_inference() {
+    assert!(rejected("let x: i32 = 'one'").contains("expected i32, found str"));
+    assert!(rejected("let x: f64 = 1.5; let y: i32 = 2; x + y").contains("expected f64, found i32"));
+    assert!(rejected("fn f(a: i32) -> i32 { a }; f('x')").contains("E0023"));
+    assert!(rejected("fn f(a) -> str { a + 1 }").contains("E0023"));
+    assert!(rejected("fn f() -> bool { return 1; true }").contains("E0023"));
+}
+
+#[test]
+fn annotations_have_to_name_a_type() {
+    for (src, name) in [
+        ("let x: strnig = 'a'", "strnig"),
+        ("fn f(a: Point) { a }", "Point"),
+        ("let x: Option<i32> = 1", "Option"),
+        ("let x: [i32 | foo] = 1", "foo"),
+        ("struct S { a: [bar] }", "bar"),
+        ("enum E { A(foo), B { b: baz } }", "baz"),
+    ] {
+        let errors = rejected(src);
+        assert!(errors.contains("E0024") && errors.contains(&format!("there's no built-in type, struct or enum called {}", name)), "{}: {}", src, errors);
+    }
+    // user types can be used before they're declared
+    assert_eq!(eval("fn f(p: Point) -> Point { p }; struct Point { x }; f(Point { x: 1 })"), "Point { x: 1 }");
+}
+
+#[test]
+fn user_and_compound_types_only_take_their_own_values() {
+    for (src, message) in [
+        ("struct Point { x }; fn f(p: Point) { p }; f(1)", "expected Point, found {number}"),
+        ("enum E { A }; let e: E = 'a'", "expected E, found str"),
+        ("struct A { x }; struct B { x }; let a: A = B { x: 1 }", "expected A, found B"),
+        ("enum E { A(i32) }; let e: E = E.A; e", "expected E, found fn(i32) -> E"),
+        ("let xs: [i32] = 1", "expected [i32], found {number}"),
+        ("let pair: (i32, str) = true", "expected (i32, str,), found bool"),
+        ("enum Option { Some(i32), None }; let o: Option<i32> = 'x'", "expected Option<i32>, found str"),
+    ] {
+        let errors = rejected(src);
+        assert!(errors.contains(message), "{}: {}", src, errors);
+    }
+    assert_eq!(eval("struct Point { x }; enum E { A(i32), B }; fn f(p: Point, e: E) -> E { e }; f(Point { x: 1 }, E.A(2))"), "E.A(2)");
+    assert_eq!(eval("enum E { A, B }; let e: E = E.B; e"), "E.B");
+    // values inference can't follow are checked at runtime instead
+    assert_eq!(eval("struct P { x }; struct Box { item }; fn f(p: P) { p.x }; f(Box { item: P { x: 3 } }.item)"), "3");
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn continue_skips_to_the_next_iteration() {
+    let run = run("let i = 0; while i < 6 { i += 1; if i == 2 { continue }; if i == 5 { break }; show i }");
+    assert_eq!(run.shown, ["1", "3", "4"]);
+}
+
+#[test]
+fn break_gives_the_loop_a_value() {
+    let run = run("let i = 0; let found = loop { i += 1; if i * i > 50 { break i } }; show found");
+    assert_eq!(run.shown, ["8"]);
+}
+
+#[test]
+fn break_cleans_up_scopes_and_temporaries() {
+    let run = run("let i = 0; let x = 1 + loop { let y = 10; { let z = 2; break y + z } }; show x; show i");
+    assert_eq!(run.shown, ["13", "0"]);
+}
+
+#[test]
+fn break_and_continue_only_leave_the_innermost_loop() {
+    let run = run("let i = 0; while i < 2 { i += 1; let j = 0; while true { j += 1; if j > 2 { break }; show j } }");
+    assert_eq!(run.shown, ["1", "2", "1", "2"]);
+}
+
+#[test]
+fn break_outside_of_a_loop_is_an_error() {
+    let run = run("break");
+    assert!(run.stderr.contains("E0015"), "{}", run.stderr);
+    assert!(run.shown.is_empty());
+}
+
+#[test]
+fn loops_outside_of_a_function_cant_be_continued_from_inside_it() {
+    let run = run("while true { fn f() { continue }; break }");
+    assert!(run.stderr.contains("'continue' outside of a loop"), "{}", run.stderr);
+}
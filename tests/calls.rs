@@ -0,0 +1,34 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn calls_inside_expressions_and_arguments() {
+    let run = run("fn inc(x) { x + 1 }; fn add(a, b) { a + b }; show 10 * add(inc(1), inc(inc(2))) + inc(0)");
+    assert_eq!(run.shown, ["61"]);
+}
+
+#[test]
+fn arguments_are_evaluated_left_to_right() {
+    let run = run("fn log(x) { show x; x }; fn three(a, b, c) { a * 100 + b * 10 + c }; show three(log(1), log(2), log(3))");
+    assert_eq!(run.shown, ["1", "2", "3", "123"]);
+}
+
+#[test]
+fn deep_and_mutual_recursion() {
+    let run = run("fn even(n) { if n == 0 { true } else { odd(n - 1) } }; fn odd(n) { if n == 0 { false } else { even(n - 1) } }; show even(1000); show odd(7)");
+    assert_eq!(run.shown, ["true", "true"]);
+}
+
+#[test]
+fn returning_from_loops_and_nested_calls_keeps_the_callers_stack() {
+    let run = run("fn find(limit) { let i = 0; loop { i += 1; if i * i >= limit { return i } } }; fn sum(a, b) { a + b }; show sum(1, sum(find(50), 100))");
+    assert_eq!(run.shown, ["109"]);
+}
+
+#[test]
+fn the_arity_is_checked_for_every_call() {
+    let run = run("fn pair(a, b) { a }; struct Holder { f }; let holder = Holder { f: pair }; show holder.f(1, 2); holder.f(1, 2, 3)");
+    assert_eq!(run.shown, ["1"]);
+    assert!(run.stderr.contains("pair requires 2 arguments but received 3"), "{}", run.stderr);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::rc::Rc;
+
+use common::run;
+use eeeee::{vm::{Captured, Value}, Engine};
+
+#[test]
+fn lambdas_can_be_called_and_passed_around() {
+    let run = run("let add = |a, b| a + b; fn twice(f, x) { f(f(x)) }; show add(2, 3); show twice(|x| x * 10, 4)");
+    assert_eq!(run.shown, ["5", "400"]);
+}
+
+#[test]
+fn closures_capture_the_scope_they_were_made_in() {
+    let run = run("fn adder(n) { |x| x + n }; let add5 = adder(5); let add7 = adder(7); show add5(1); show add7(1); show add5(2)");
+    assert_eq!(run.shown, ["6", "8", "7"]);
+}
+
+#[test]
+fn captured_variables_are_shared_with_the_closure() {
+    let run = run("fn counter() { let count = 0; || { count += 1; count } }; let next = counter(); next(); next(); show next(); let other = counter(); show other()");
+    assert_eq!(run.shown, ["3", "1"]);
+}
+
+#[test]
+fn lambdas_without_parameters_and_with_block_bodies() {
+    let run = run("let x = 2; let f = || { let y = x * 3; y + 1 }; x = 5; show f()");
+    assert_eq!(run.shown, ["16"]);
+}
+
+#[test]
+fn named_functions_are_closures_too() {
+    let run = run("let base = 100; fn outer() { fn inner(x) { x + base }; inner }; show outer()(1)");
+    assert_eq!(run.shown, ["101"]);
+}
+
+#[test]
+fn functions_declared_in_a_scope_dont_keep_it_alive() {
+    let mut engine = Engine::new();
+    let inner = engine.eval("fn make() { let n = 1; fn inner() { n += 1; n }; inner }; make()", "test").unwrap();
+    let scope = match &inner {
+        Value::Closure(closure) => match closure.environment.last() {
+            Some(Captured::Strong(scope)) => Rc::downgrade(scope),
+            other => panic!("expected the scope of make, got {:?}", other),
+        },
+        other => panic!("expected a closure, got {:?}", other),
+    };
+    assert!(scope.upgrade().is_some());
+    drop(inner);
+    assert!(scope.upgrade().is_none());
+}
+
+#[test]
+fn functions_that_escape_their_scope_still_work() {
+    let run = run("fn counter() { let count = 0; fn next() { count += 1; count }; next }; let next = counter(); next(); show next(); let again = next; show again()");
+    assert_eq!(run.shown, ["2", "3"]);
+}
@@ -0,0 +1,24 @@
+//! runs the `eeeee` binary on a script. it always runs `testing.txt` from its working directory,
+//! so every script gets a directory of its own
+
+#![allow(dead_code)]
+
+use std::{path::PathBuf, process::Command, sync::atomic::{AtomicUsize, Ordering}};
+
+pub struct Run {
+    /// what the script `show`ed, one value per line
+    pub shown: Vec<String>,
+    /// everything else the binary printed, diagnostics included
+    pub stderr: String,
+}
+
+pub fn run(src: &str) -> Run {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!("eeeee-test-{}-{}", std::process::id(), RUNS.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("testing.txt"), src).unwrap();
+    let output = Command::new(PathBuf::from(env!("CARGO_BIN_EXE_eeeee"))).current_dir(&dir).output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let shown = String::from_utf8(output.stdout).unwrap().lines().map(str::to_owned).collect();
+    Run { shown, stderr: String::from_utf8(output.stderr).unwrap() }
+}
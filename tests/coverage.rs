@@ -0,0 +1,57 @@
+use std::process::Command;
+
+const PROGRAM: &str = "\
+fn sign(n) {
+    if n < 0 {
+        0 - 1
+    } else {
+        1
+    }
+};
+let i = 0;
+while i < 3 {
+    i += 1
+};
+sign(i);
+fn unused() {
+    show 1
+}";
+
+/// runs `eeeee coverage` on `PROGRAM`, and reads back the LCOV tracefile it wrote
+fn tracefile() -> String {
+    let dir = std::env::temp_dir().join(format!("eeeee-coverage-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("program.eee"), PROGRAM).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_eeeee")).current_dir(&dir).args(["coverage", "program.eee", "coverage.info"]).output().unwrap();
+    assert!(output.status.success());
+    let tracefile = std::fs::read_to_string(dir.join("coverage.info")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    tracefile
+}
+
+#[test]
+fn lcov_tracefile() {
+    // the `if` never took its first branch, the loop's condition held three times and then jumped out.
+    // the lines of `0 - 1` and of the function that's never called never ran
+    assert_eq!(tracefile(), "\
+TN:
+SF:program.eee
+BRDA:2,0,0,0
+BRDA:2,0,1,1
+BRDA:9,1,0,3
+BRDA:9,1,1,1
+BRF:4
+BRH:3
+DA:2,1
+DA:3,0
+DA:5,1
+DA:8,1
+DA:9,4
+DA:10,3
+DA:12,1
+DA:14,0
+LF:8
+LH:6
+end_of_record
+");
+}
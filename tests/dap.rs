@@ -0,0 +1,66 @@
+use std::{io::Cursor, sync::atomic::{AtomicUsize, Ordering}};
+
+use serde_json::{json, Value as Json};
+
+const PROGRAM: &str = "\
+let x = 1;
+show x + 1;
+show x + 2";
+
+fn frame(messages: &[Json]) -> Vec<u8> {
+    messages.iter().enumerate().flat_map(|(seq, message)| {
+        let mut message = message.clone();
+        message["seq"] = json!(seq + 1);
+        message["type"] = json!("request");
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes()
+    }).collect()
+}
+
+fn unframe(output: &[u8]) -> Vec<Json> {
+    let output = String::from_utf8(output.to_vec()).unwrap();
+    output.split("Content-Length: ").skip(1).map(|message| {
+        let (_, body) = message.split_once("\r\n\r\n").unwrap();
+        serde_json::from_str(body).unwrap()
+    }).collect()
+}
+
+/// serves a session on `PROGRAM` that launches with `stop_on_entry`, then sends `requests`.
+/// gives back the events, by name, in the order they were sent
+fn session(stop_on_entry: bool, requests: &[Json]) -> Vec<String> {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!("eeeee-dap-{}-{}.eee", std::process::id(), RUNS.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&path, PROGRAM).unwrap();
+    let mut messages = vec![
+        json!({ "command": "initialize", "arguments": {} }),
+        json!({ "command": "launch", "arguments": { "program": path.to_str().unwrap(), "stopOnEntry": stop_on_entry } }),
+        json!({ "command": "configurationDone" }),
+    ];
+    messages.extend(requests.iter().cloned());
+    let mut output = vec![];
+    eeeee::dap::serve(Cursor::new(frame(&messages)), &mut output).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    unframe(&output).into_iter().filter(|message| message["type"] == "event").map(|event| {
+        match event["body"]["output"].as_str() {
+            Some(output) => format!("output {}", output.trim()),
+            None => event["event"].as_str().unwrap().to_owned(),
+        }
+    }).collect()
+}
+
+#[test]
+fn running_to_the_end() {
+    assert_eq!(session(false, &[json!({ "command": "disconnect" })]), ["initialized", "output 2", "output 3", "exited", "terminated"]);
+}
+
+#[test]
+fn disconnecting_while_paused_stops_the_program_and_returns() {
+    let events = session(true, &[json!({ "command": "next", "arguments": { "threadId": 1 } }), json!({ "command": "disconnect" })]);
+    // it stopped on the second line, before showing anything
+    assert_eq!(events, ["initialized", "stopped", "stopped", "exited", "terminated"]);
+}
+
+#[test]
+fn the_input_ending_while_paused_stops_the_program_and_returns() {
+    assert_eq!(session(true, &[]), ["initialized", "stopped", "exited", "terminated"]);
+}
@@ -0,0 +1,113 @@
+use std::{io::Write, process::{Command, Stdio}, sync::atomic::{AtomicUsize, Ordering}};
+
+use eeeee::{engine, loader::{self, Loader, Span}, vm::{self, DebugCommand, Debugger, VM}};
+
+const PROGRAM: &str = "\
+fn add(a, b) {
+    let sum = a + b;
+    sum
+};
+let x = 1;
+let y = add(x, 2);
+show y";
+
+/// steps with the same command every time, and remembers where it paused
+struct Stepper {
+    line_starts: Vec<u32>,
+    command: DebugCommand,
+    /// (line, how many functions were running)
+    paused: Vec<(usize, usize)>,
+}
+
+impl Debugger for Stepper {
+    fn line(&mut self, span: Span) -> usize {
+        loader::line_of(&self.line_starts, span.start) + 1
+    }
+
+    fn has_breakpoint(&mut self, _: usize, line: usize) -> bool {
+        line == 2
+    }
+
+    fn paused(&mut self, vm: &mut VM) -> DebugCommand {
+        let line = self.line(vm.program().spans[vm.ip()].unwrap());
+        self.paused.push((line, vm.backtrace().len()));
+        self.command
+    }
+}
+
+fn step(command: DebugCommand) -> Vec<(usize, usize)> {
+    let mut loader = Loader::new();
+    let file = loader.load_from_memory(PROGRAM.to_owned(), "test".to_owned());
+    let (program, _) = engine::compile(&mut loader, file, &[]).unwrap();
+    let mut stepper = Stepper { line_starts: loader.get_file(file).line_starts(), command, paused: vec![] };
+    let value = VM::new(program).debug(&mut stepper, DebugCommand::StepInto).unwrap().unwrap();
+    assert_eq!(vm::display(&value), "3");
+    stepper.paused
+}
+
+#[test]
+fn stepping_into_calls() {
+    assert_eq!(step(DebugCommand::StepInto), [(5, 1), (6, 1), (2, 2), (3, 2), (6, 1), (7, 1)]);
+}
+
+#[test]
+fn stepping_over_calls_stops_at_breakpoints_inside_them() {
+    assert_eq!(step(DebugCommand::StepOver), [(5, 1), (6, 1), (2, 2), (3, 2), (6, 1), (7, 1)]);
+}
+
+#[test]
+fn continuing_stops_at_breakpoints_only() {
+    assert_eq!(step(DebugCommand::Continue), [(5, 1), (2, 2)]);
+}
+
+#[test]
+fn stepping_out() {
+    assert_eq!(step(DebugCommand::StepOut), [(5, 1), (2, 2), (6, 1)]);
+}
+
+/// runs `eeeee debug` on `PROGRAM`, typing `input` at the prompt
+fn debug(input: &str) -> String {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!("eeeee-debugger-{}-{}.eee", std::process::id(), RUNS.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&path, PROGRAM).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_eeeee")).arg("debug").arg(&path)
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn the_prompt() {
+    let output = debug("b 3\nc\np sum * 10\nbt\nscopes\nf 1\np x\nc\n");
+    for expected in ["paused at", "3 |     sum", "(eeeee) 30\n", "#0 in add at", "#1 in <top level> at", "scope 0:\n    sum = 3\nscope 1:\n    a = 1\n    b = 2\n", "(eeeee) 1\n", "finished with 3"] {
+        assert!(output.contains(expected), "{:?} isn't in\n{}", expected, output);
+    }
+}
+
+#[test]
+fn stopping_ends_the_program_where_it_is() {
+    let mut loader = Loader::new();
+    let file = loader.load_from_memory(PROGRAM.to_owned(), "test".to_owned());
+    let (program, _) = engine::compile(&mut loader, file, &[]).unwrap();
+    let mut stepper = Stepper { line_starts: loader.get_file(file).line_starts(), command: DebugCommand::Stop, paused: vec![] };
+    let mut vm = VM::new(program);
+    assert!(vm.debug(&mut stepper, DebugCommand::Continue).unwrap().is_none());
+    assert_eq!(stepper.paused, [(2, 2)]);
+    assert_eq!(vm.backtrace().len(), 2);
+}
+
+#[test]
+fn quitting() {
+    let output = debug("n\nq\n");
+    assert!(output.contains(":5"), "{}", output);
+    assert!(!output.contains("finished"), "{}", output);
+}
+
+#[test]
+fn the_end_of_the_input_quits_too() {
+    let output = debug("n\n");
+    assert!(output.contains(":6"), "{}", output);
+    assert!(!output.contains("finished"), "{}", output);
+}
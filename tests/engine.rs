@@ -0,0 +1,63 @@
+use eeeee::{engine::EngineError, vm::{self, Value}, Engine};
+
+fn eval(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Ok(value) => vm::display(&value),
+        Err(error) => panic!("{}: {:?}", src, error),
+    }
+}
+
+fn diagnostics(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Err(EngineError::Diagnostics(errors)) => format!("{:?}", errors),
+        other => panic!("{}: expected diagnostics, got {:?}", src, other),
+    }
+}
+
+#[test]
+fn scripts_evaluate_to_their_last_expression() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "let x = 20; x * 2 + 2"), "42");
+    assert_eq!(eval(&mut engine, "'hi'"), "hi");
+    assert_eq!(eval(&mut engine, "let y = 1"), "<nothing>");
+}
+
+#[test]
+fn top_level_declarations_become_globals() {
+    let mut engine = Engine::new();
+    eval(&mut engine, "let count = 1; fn bump(by) { count += by; count }");
+    assert_eq!(eval(&mut engine, "bump(10); bump(5)"), "16");
+    assert_eq!(engine.get_global("count").map(|value| vm::display(&value)).as_deref(), Some("16"));
+    assert_eq!(eval(&mut engine, "count = 0; let count = 100; bump(1)"), "1");
+    assert_eq!(eval(&mut engine, "count"), "100");
+}
+
+#[test]
+fn the_host_can_set_globals_and_call_functions() {
+    let mut engine = Engine::new();
+    engine.set_global("name", Value::Str("world".to_owned()));
+    eval(&mut engine, "fn pick(a, b) { if a { b } else { name } }");
+    assert_eq!(engine.call("pick", vec![Value::Bool(false), Value::Str("x".to_owned())]).map(|value| vm::display(&value)).unwrap(), "world");
+    assert_eq!(engine.call("pick", vec![Value::Bool(true), Value::Str("x".to_owned())]).map(|value| vm::display(&value)).unwrap(), "x");
+    engine.set_global("name", Value::Str("again".to_owned()));
+    assert_eq!(eval(&mut engine, "pick(false, 1)"), "again");
+}
+
+#[test]
+fn closures_outlive_the_script_that_made_them() {
+    let mut engine = Engine::new();
+    eval(&mut engine, "fn counter() { let n = 0; || { n += 1; n } }; let next = counter()");
+    eval(&mut engine, "next(); next()");
+    assert_eq!(engine.call("next", vec![]).map(|value| vm::display(&value)).unwrap(), "3");
+}
+
+#[test]
+fn errors_come_back_as_diagnostics() {
+    let mut engine = Engine::new();
+    assert!(diagnostics(&mut engine, "undeclared = 1").contains("E0021"));
+    assert!(diagnostics(&mut engine, "let f = 1; nowhere()").contains("nowhere is not defined"));
+    assert!(diagnostics(&mut engine, "let = 1").contains("E0012"));
+    assert!(matches!(engine.call("nothing_here", vec![]), Err(EngineError::Diagnostics(_))));
+    // a failed script still declared what it got to
+    assert_eq!(eval(&mut engine, "f"), "1");
+}
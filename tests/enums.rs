@@ -0,0 +1,58 @@
+mod common;
+
+use common::run;
+
+const SHAPE: &str = "enum Shape { Point, Circle(f64), Rect { width: f64, height: f64 } };";
+
+#[test]
+fn variants_are_constructed_through_the_enum() {
+    let run = run(&format!("{SHAPE} show Shape.Point; show Shape.Circle(2); show Shape.Rect {{ height: 3, width: 4 }}"));
+    assert_eq!(run.shown, ["Shape.Point", "Shape.Circle(2)", "Shape.Rect { width: 4, height: 3 }"]);
+}
+
+#[test]
+fn variant_patterns_destructure_payloads() {
+    let run = run(&format!("{SHAPE} fn area(shape) {{ match shape {{ Shape.Point => 0, Shape.Circle(r) => 3 * r * r, Shape.Rect {{ width, height: h }} => width * h }} }};
+        show area(Shape.Point); show area(Shape.Circle(2)); show area(Shape.Rect {{ width: 4, height: 3 }})"));
+    assert_eq!(run.shown, ["0", "12", "12"]);
+}
+
+#[test]
+fn payload_patterns_can_be_literals_and_wildcards() {
+    let run = run("enum E { Pair(f64, f64) }; fn f(e) { match e { E.Pair(0, _) => 'left zero', E.Pair(_, 0) => 'right zero', E.Pair(a, b) => a + b } };
+        show f(E.Pair(0, 5)); show f(E.Pair(5, 0)); show f(E.Pair(2, 3))");
+    assert_eq!(run.shown, ["left zero", "right zero", "5"]);
+}
+
+#[test]
+fn struct_like_payload_fields_can_be_read() {
+    let run = run(&format!("{SHAPE} let r = Shape.Rect {{ width: 4, height: 3 }}; show r.width + r.height"));
+    assert_eq!(run.shown, ["7"]);
+}
+
+#[test]
+fn variants_of_different_enums_dont_match() {
+    let run = run("enum A { X }; enum B { X }; show match B.X { A.X => 'a', B.X => 'b' }");
+    assert_eq!(run.shown, ["b"]);
+}
+
+#[test]
+fn constructor_arguments_are_checked_against_the_payload_annotations() {
+    for src in ["enum E { A(i32) }; show E.A('str')", "enum E { B { y: i32 } }; show E.B { y: 'str' }"] {
+        let run = run(src);
+        assert!(run.stderr.contains("expected i32, found str"), "{}", run.stderr);
+        assert!(run.shown.is_empty());
+    }
+}
+
+#[test]
+fn struct_like_variant_fields_dont_need_annotations() {
+    let run = run("enum E { B { y }, C { x: i32, label } }; show E.B { y: 'any' }; show E.C { x: 1, label: 2 }");
+    assert_eq!(run.shown, ["E.B { y: any }", "E.C { x: 1, label: 2 }"]);
+}
+
+#[test]
+fn struct_like_variant_fields_cant_be_declared_twice() {
+    let run = run("enum E { B { y, y } }");
+    assert!(run.stderr.contains("E0019"), "{}", run.stderr);
+}
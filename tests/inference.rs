@@ -0,0 +1,63 @@
+use eeeee::{engine::EngineError, vm, Engine};
+
+fn eval(src: &str) -> String {
+    match Engine::new().eval(src, "test") {
+        Ok(value) => vm::display(&value),
+        Err(error) => panic!("{}: {:?}", src, error),
+    }
+}
+
+/// the compile time errors of `src`, which must not run at all
+fn mismatched(src: &str) -> String {
+    let mut engine = Engine::new();
+    match engine.eval(&format!("show 'ran'; {}", src), "test") {
+        Err(EngineError::Diagnostics(errors)) => {
+            let errors = format!("{:?}", errors);
+            assert!(errors.contains("mismatched types") && errors.contains("E0023"), "{}: {}", src, errors);
+            errors
+        },
+        other => panic!("{}: expected a type error, got {:?}", src, other),
+    }
+}
+
+#[test]
+fn operators_check_their_operands() {
+    assert!(mismatched("1 + 'one'").contains("expected {number}, found str"));
+    mismatched("true - 1");
+    mismatched("!3");
+    mismatched("let x = 'a'; x * 2");
+}
+
+#[test]
+fn conditions_are_bools_and_branches_agree() {
+    mismatched("if 1 { 2 } else { 3 }");
+    mismatched("while 'yes' { }");
+    mismatched("let x = if true { 1 } else { 'one' }; x");
+    mismatched("match 1 { 1 => 'one', _ => 2 }");
+    // a `while` can end without a `break`, so it can't break with a value
+    mismatched("while true { break 1 }");
+}
+
+#[test]
+fn functions_are_checked_where_they_are_called() {
+    mismatched("fn inc(x) { x + 1 }; inc('a')");
+    mismatched("fn call(f) { f(1) }; call(|a, b| a)");
+    mismatched("fn early(x) { if x { return 1 }; 'two' }");
+    mismatched("fn f() { 1 }; f() + 'a'");
+}
+
+#[test]
+fn declarations_are_generic() {
+    assert_eq!(eval("fn id(x) { x }; fn pair(a, b) { b }; pair(id(1), id('one'))"), "one");
+    assert_eq!(eval("fn twice(f, x) { f(f(x)) }; twice(|s| s, 'a'); twice(_ * 2, 3)"), "12");
+    assert_eq!(eval("fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }; fact(5)"), "120");
+}
+
+#[test]
+fn what_cant_be_known_is_checked_at_runtime() {
+    // struct fields, variant payloads and globals from earlier scripts are dynamic
+    assert_eq!(eval("struct Box { value }; let b = Box { value: 1 }; b.value = 'one'; b.value"), "one");
+    let mut engine = Engine::new();
+    engine.eval("let anything = 1", "first").unwrap();
+    assert_eq!(vm::display(&engine.eval("anything = 'now a str'; anything", "second").unwrap()), "now a str");
+}
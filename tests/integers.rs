@@ -0,0 +1,64 @@
+use eeeee::{engine::EngineError, vm::{self, Overflow}, Engine};
+
+fn eval(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Ok(value) => vm::display(&value),
+        Err(error) => panic!("{}: {:?}", src, error),
+    }
+}
+
+fn diagnostics(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Err(EngineError::Diagnostics(errors)) => format!("{:?}", errors),
+        other => panic!("{}: expected diagnostics, got {:?}", src, other),
+    }
+}
+
+#[test]
+fn integer_division_is_integer_division() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "7 / 2"), "3");
+    assert_eq!(eval(&mut engine, "-7 / 2"), "-3");
+    assert_eq!(eval(&mut engine, "7.0 / 2.0"), "3.5");
+    assert!(diagnostics(&mut engine, "1 / 0").contains("division by zero"));
+}
+
+#[test]
+fn large_literals_keep_their_precision() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "let x: u64 = 18446744073709551615; x"), "18446744073709551615");
+    assert_eq!(eval(&mut engine, "let x: i64 = 0x7fffffffffffffff; x"), "9223372036854775807");
+}
+
+#[test]
+fn checked_overflow_stops_the_program() {
+    let mut engine = Engine::new();
+    assert!(diagnostics(&mut engine, "let x: u8 = 255; x + 1").contains("addition overflowed u8"));
+    assert!(diagnostics(&mut engine, "let x: u8 = 200; x as i8").contains("u8 200 doesn't fit in i8"));
+}
+
+#[test]
+fn wrapping_overflow_wraps_around() {
+    let mut engine = Engine::new();
+    engine.set_overflow(Overflow::Wrapping);
+    assert_eq!(eval(&mut engine, "let x: u8 = 255; x + 1"), "0");
+    assert_eq!(eval(&mut engine, "let x: i8 = -128; x - 1"), "127");
+    assert_eq!(eval(&mut engine, "let x: i32 = 2147483647; x * 2"), "-2");
+    assert_eq!(eval(&mut engine, "let x: u8 = 200; x as i8"), "-56");
+}
+
+#[test]
+fn mixing_integers_and_floats_needs_as() {
+    let mut engine = Engine::new();
+    let error = diagnostics(&mut engine, "include 'std/mod.eee'; let x: i32 = 16; x < std.math.sqrt(16.0)");
+    assert!(error.contains("can't mix i32 and f64, convert one of them with `as`"), "{}", error);
+    assert_eq!(eval(&mut engine, "include 'std/mod.eee'; let x: i32 = 16; (x as f64) <= std.math.sqrt(256.0)"), "true");
+    assert!(diagnostics(&mut engine, "let x: u8 = 3; let y: i32 = 4; x + y").contains("expected u8, found i32"));
+}
+
+#[test]
+fn untyped_literals_take_the_type_of_the_other_operand() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "fn add(a, b) { a + b }; add(1, 2.5)"), "3.5");
+    assert_eq!(eval(&mut engine, "let r: f64 = 16.0; fn lt(a, b) { a < b }; lt(3, r)"), "true");
+}
@@ -0,0 +1,76 @@
+use eeeee::{engine::EngineError, vm::{self, Limit, Limits, RuntimeErrorKind, Value}, Engine};
+
+fn limit(result: Result<Value, EngineError>) -> Limit {
+    match result {
+        Err(EngineError::LimitExceeded(error)) => match error.kind {
+            RuntimeErrorKind::LimitExceeded(limit) => limit,
+            kind => panic!("expected a limit, got {:?}", kind),
+        },
+        other => panic!("expected a limit, got {:?}", other),
+    }
+}
+
+#[test]
+fn running_out_of_fuel_can_be_resumed() {
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { fuel: Some(1000), ..Limits::default() });
+    assert_eq!(limit(engine.eval("let i = 0; while i < 1000 { i += 1 }; i * 2", "test")), Limit::Fuel);
+    let mut resumed = 0;
+    let value = loop {
+        match engine.resume(1000) {
+            Err(EngineError::LimitExceeded(_)) => resumed += 1,
+            result => break result.unwrap(),
+        }
+    };
+    assert_eq!(vm::display(&value), "2000");
+    assert!(resumed > 0);
+    assert!(matches!(engine.resume(1000), Err(EngineError::NothingToResume)));
+}
+
+#[test]
+fn every_script_gets_all_of_its_fuel() {
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { fuel: Some(500), ..Limits::default() });
+    for _ in 0..5 {
+        engine.eval("let i = 0; while i < 10 { i += 1 }", "test").unwrap();
+    }
+    assert_eq!(limit(engine.eval("fn forever() { while true { } }; forever()", "test")), Limit::Fuel);
+    // a new script forgets the one that was stopped
+    engine.eval("1", "test").unwrap();
+    assert!(matches!(engine.resume(1000), Err(EngineError::NothingToResume)));
+}
+
+#[test]
+fn calls_from_the_host_are_limited_too() {
+    let mut engine = Engine::new();
+    engine.eval("fn spin(n) { let i = 0; while i < n { i += 1 }; i }", "test").unwrap();
+    engine.set_limits(Limits { fuel: Some(100), ..Limits::default() });
+    assert_eq!(limit(engine.call("spin", vec![Value::Num(vm::Number::I32(1000))])), Limit::Fuel);
+    let mut value = engine.resume(1_000_000);
+    while let Err(EngineError::LimitExceeded(_)) = value {
+        value = engine.resume(1_000_000);
+    }
+    assert_eq!(vm::display(&value.unwrap()), "1000");
+}
+
+#[test]
+fn call_and_scope_depth() {
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { max_call_depth: Some(50), ..Limits::default() });
+    engine.eval("fn down(n) { if n == 0 { 0 } else { down(n - 1) } }; down(40)", "test").unwrap();
+    assert_eq!(limit(engine.eval("down(60)", "test")), Limit::CallDepth(50));
+    // raising the limit lets the same script finish
+    engine.set_limits(Limits { max_call_depth: Some(100), ..Limits::default() });
+    assert_eq!(vm::display(&engine.resume(0).unwrap()), "0");
+
+    engine.set_limits(Limits { max_scope_depth: Some(4), ..Limits::default() });
+    assert_eq!(limit(engine.eval("{ { { { { 1 } } } } }", "test")), Limit::ScopeDepth(4));
+}
+
+#[test]
+fn memory() {
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { max_heap: Some(10_000), ..Limits::default() });
+    let result = engine.eval("let f = || 0; while true { let g = f; f = || g() + 1 }", "test");
+    assert_eq!(limit(result), Limit::Heap(10_000));
+}
@@ -0,0 +1,58 @@
+use eeeee::{engine::EngineError, vm, Engine};
+
+fn eval(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Ok(value) => vm::display(&value),
+        Err(error) => panic!("{}: {:?}", src, error),
+    }
+}
+
+fn diagnostics(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Err(EngineError::Diagnostics(errors)) => format!("{:?}", errors),
+        other => panic!("{}: expected diagnostics, got {:?}", src, other),
+    }
+}
+
+/// checks that `src` has exactly one "literal out of range" error, labelled `label` and pointing at `start..end`
+fn out_of_range(engine: &mut Engine, src: &str, label: &str, (start, end): (usize, usize)) {
+    let errors = diagnostics(engine, src);
+    let expected = format!(r#"[Error {{ message: "literal out of range", pieces: [Some("{}")], spans: [Span {{ file: "#, label);
+    assert!(errors.starts_with(&expected), "{}: {}", src, errors);
+    assert!(errors.ends_with(&format!(r#"start: {}, end: {} }}], severity: Error, code: "E0025" }}]"#, start, end)), "{}: {}", src, errors);
+}
+
+#[test]
+fn type_hints_pin_the_literal_type() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "255u8"), "255");
+    assert_eq!(eval(&mut engine, "-128i8"), "-128");
+    assert_eq!(eval(&mut engine, "3.5f32"), "3.5");
+    assert_eq!(eval(&mut engine, "let a = 1u8; let b = 2; a + b"), "3");
+    assert!(diagnostics(&mut engine, "let a = 1u8; let b = 2i32; a + b").contains("expected u8, found i32"));
+}
+
+#[test]
+fn out_of_range_literals_are_reported_at_the_literal() {
+    let mut engine = Engine::new();
+    out_of_range(&mut engine, "300u8", "this doesn't fit in u8, which goes from 0 to 255", (0, 5));
+    out_of_range(&mut engine, "let x = 4294967296u32; x", "this doesn't fit in u32, which goes from 0 to 4294967295", (8, 21));
+    out_of_range(&mut engine, "-129i8", "this doesn't fit in i8, which goes from -128 to 127", (0, 6));
+    out_of_range(&mut engine, "let x: u8 = 256; x", "this doesn't fit in u8, which goes from 0 to 255", (12, 15));
+}
+
+#[test]
+fn hex_and_binary_literals_are_checked_too() {
+    let mut engine = Engine::new();
+    out_of_range(&mut engine, "0x1ffu8", "this doesn't fit in u8, which goes from 0 to 255", (0, 7));
+    out_of_range(&mut engine, "0b111111111u8", "this doesn't fit in u8, which goes from 0 to 255", (0, 13));
+    out_of_range(&mut engine, "0xffffffffffffffffffffffffffffffffff", "this doesn't fit in i32, which goes from -2147483648 to 2147483647", (0, 36));
+    assert_eq!(eval(&mut engine, "0xffu8"), "255");
+    assert_eq!(eval(&mut engine, "0b1010"), "10");
+}
+
+#[test]
+fn floats_too_big_for_their_type() {
+    let mut engine = Engine::new();
+    out_of_range(&mut engine, "1000000000000000000000000000000000000000.0f32", &format!("this doesn't fit in f32, which goes up to {}", f32::MAX), (0, 45));
+}
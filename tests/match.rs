@@ -0,0 +1,35 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn prefix_match_picks_the_first_arm_that_matches() {
+    let run = run("fn name(n) { match n { 0 => 'zero', 1 => 'one', -1 => 'minus one', _ => 'many' } }; show name(1); show name(0 - 1); show name(7); show name(0)");
+    assert_eq!(run.shown, ["one", "minus one", "many", "zero"]);
+}
+
+#[test]
+fn postfix_match() {
+    let run = run("let x = (2 + 1).match { 3 => 'three', _ => 'other' }; show x; show 'b'.match { 'a' => 1, 'b' => 2 }; show false.match { true => 1, false => 0 }");
+    assert_eq!(run.shown, ["three", "2", "0"]);
+}
+
+#[test]
+fn bindings_capture_the_value_in_their_arm_only() {
+    let run = run("let n = 10; show match 5 { 0 => 0, n => n * 2 }; show n");
+    assert_eq!(run.shown, ["10", "10"]);
+}
+
+#[test]
+fn matches_can_be_nested_and_used_in_expressions() {
+    let run = run("fn f(a, b) { 1 + match a { 0 => match b { 0 => 10, _ => 20 }, _ => 30 } }; show f(0, 0); show f(0, 1); show f(1, 0)");
+    assert_eq!(run.shown, ["11", "21", "31"]);
+}
+
+#[test]
+fn patterns_have_to_be_literals_or_names() {
+    let run = run("match 1 { * => 2 }");
+    assert!(run.stderr.contains("E0018"), "{}", run.stderr);
+    let run = common::run("match 1 { - x => 2 }");
+    assert!(run.stderr.contains("expected a number after '-' in pattern"), "{}", run.stderr);
+}
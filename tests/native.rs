@@ -0,0 +1,58 @@
+use std::{cell::RefCell, rc::Rc};
+
+use eeeee::{engine::EngineError, vm::{self, RuntimeError, RuntimeErrorKind, Value}, Engine};
+
+fn eval(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Ok(value) => vm::display(&value),
+        Err(error) => panic!("{}: {:?}", src, error),
+    }
+}
+
+fn join(arguments: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(arguments.iter().map(vm::display).collect::<Vec<_>>().join("-")))
+}
+
+#[test]
+fn scripts_call_native_functions_like_any_other() {
+    let mut engine = Engine::new();
+    engine.register_fn("join", join);
+    assert_eq!(eval(&mut engine, "join('a', true, 'c')"), "a-true-c");
+    assert_eq!(eval(&mut engine, "fn apply(f) { f('x', 'y') }; apply(join)"), "x-y");
+    assert_eq!(eval(&mut engine, "join"), "<native function join>");
+}
+
+#[test]
+fn native_functions_go_in_modules() {
+    let mut engine = Engine::new();
+    engine.register_module_fn("text", "join", join);
+    engine.register_module_fn("text", "first", |arguments| Ok(arguments.first().cloned().unwrap_or(Value::Nothing)));
+    assert_eq!(eval(&mut engine, "text.first(text.join('a', 'b'), 'c')"), "a-b");
+}
+
+#[test]
+fn native_functions_keep_their_own_state() {
+    let mut engine = Engine::new();
+    let calls = Rc::new(RefCell::new(vec![]));
+    let seen = calls.clone();
+    engine.register_fn("record", move |arguments| {
+        seen.borrow_mut().extend(arguments.iter().map(vm::display));
+        Ok(Value::Nothing)
+    });
+    eval(&mut engine, "let i = 0; while i < 3 { record(i); i += 1 }");
+    assert_eq!(*calls.borrow(), ["0", "1", "2"]);
+}
+
+#[test]
+fn native_errors_are_runtime_errors_of_the_script() {
+    let mut engine = Engine::new();
+    engine.register_fn("fail", |_| Err(RuntimeError { kind: RuntimeErrorKind::Native("it went wrong".to_owned()), trace: vec![] }));
+    match engine.eval("fn outer() { fail() }; outer()", "test") {
+        Err(EngineError::Diagnostics(errors)) => {
+            let errors = format!("{:?}", errors);
+            assert!(errors.contains("it went wrong") && errors.contains("E0022"), "{}", errors);
+            assert!(errors.contains("in outer"), "{}", errors);
+        },
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
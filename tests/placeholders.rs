@@ -0,0 +1,35 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn a_placeholder_inside_an_argument_makes_that_argument_a_lambda() {
+    let run = run("fn apply(f, x) { f(x) }; show apply(_ * 3, 5); show apply(_ + 1, 1)");
+    assert_eq!(run.shown, ["15", "2"]);
+}
+
+#[test]
+fn a_bare_placeholder_argument_makes_the_call_a_lambda() {
+    let run = run("fn sub(a, b) { a - b }; let minus_one = sub(_, 1); let from_ten = sub(10, _); show minus_one(5); show from_ten(3)");
+    assert_eq!(run.shown, ["4", "7"]);
+}
+
+#[test]
+fn placeholders_stop_at_explicit_lambdas() {
+    let run = run("fn apply(f, x) { f(x) }; show apply(|x| apply(_ * 2, x), 4)");
+    assert_eq!(run.shown, ["8"]);
+}
+
+#[test]
+fn more_than_one_placeholder_is_ambiguous() {
+    for src in ["fn f(g) { g }; f(_ + _)", "fn f(a, b) { a }; f(_, _)", "fn f(a) { a }; _(_)"] {
+        let run = run(src);
+        assert!(run.stderr.contains("ambiguous use of '_'"), "{}: {}", src, run.stderr);
+    }
+}
+
+#[test]
+fn placeholders_outside_of_arguments_are_an_error() {
+    let run = run("let f = _ + 1");
+    assert!(run.stderr.contains("E0017"), "{}", run.stderr);
+}
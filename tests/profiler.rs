@@ -0,0 +1,61 @@
+use std::{collections::HashMap, process::Command};
+
+use eeeee::{engine, loader::Loader, vm::VM};
+
+const PROGRAM: &str = "\
+fn inner(n) { n * 2 };
+fn outer(n) { inner(n) + inner(n + 1) };
+fn countdown(n) { if n > 0 { countdown(n - 1) } else { outer(n) } };
+outer(1);
+countdown(2)";
+
+/// runs `eeeee profile` on `PROGRAM`, and reads back the folded stacks it wrote
+fn folded() -> HashMap<String, u128> {
+    let dir = std::env::temp_dir().join(format!("eeeee-profiler-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("program.eee"), PROGRAM).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_eeeee")).current_dir(&dir).args(["profile", "program.eee", "stacks.folded"]).output().unwrap().status;
+    assert!(status.success());
+    let folded = std::fs::read_to_string(dir.join("stacks.folded")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    folded.lines().map(|line| {
+        let (stack, weight) = line.rsplit_once(' ').unwrap_or_else(|| panic!("{:?} has no weight", line));
+        (stack.to_owned(), weight.parse().unwrap_or_else(|_| panic!("{:?} isn't weighted in nanoseconds", line)))
+    }).collect()
+}
+
+#[test]
+fn folded_stacks_have_a_line_for_every_call_path() {
+    let folded = folded();
+    let mut stacks = folded.keys().map(String::as_str).collect::<Vec<_>>();
+    stacks.sort_unstable();
+    assert_eq!(stacks, [
+        "<top level>",
+        "<top level>;countdown",
+        "<top level>;countdown;countdown",
+        "<top level>;countdown;countdown;countdown",
+        "<top level>;countdown;countdown;countdown;outer",
+        "<top level>;countdown;countdown;countdown;outer;inner",
+        "<top level>;outer",
+        "<top level>;outer;inner",
+    ]);
+}
+
+#[test]
+fn costs_are_split_between_the_stacks() {
+    let mut loader = Loader::new();
+    let file = loader.load_from_memory(PROGRAM.to_owned(), "test".to_owned());
+    let (program, _) = engine::compile(&mut loader, file, &[]).unwrap();
+    let mut vm = VM::new(program);
+    vm.enable_profiling();
+    vm.run().unwrap();
+    let profile = vm.take_profile().unwrap();
+    let instructions = |stack: &[&str]| profile.stacks.iter().position(|names| names == stack).map(|index| profile.stack_costs[index].instructions).unwrap();
+    // `inner` runs the same instructions every time it's called, and it was called twice from each `outer`
+    let inner = instructions(&["<top level>", "outer", "inner"]);
+    assert_eq!(inner, instructions(&["<top level>", "countdown", "countdown", "countdown", "outer", "inner"]));
+    assert_eq!(inner % 2, 0);
+    let by_stack = profile.stack_costs.iter().map(|cost| cost.instructions).sum::<u64>();
+    let by_span = profile.spans.values().map(|cost| cost.instructions).sum::<u64>();
+    assert!(by_span <= by_stack && by_span > 0);
+}
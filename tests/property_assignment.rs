@@ -0,0 +1,31 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn compound_assignment_through_a_path() {
+    let run = run("struct Size { width, height }; struct Screen { size }; let screen = Screen { size: Size { width: 640, height: 480 } };
+        screen.size.width += 640; screen.size.height *= 2; screen.size.width -= 1; show screen.size.width; show screen.size.height");
+    assert_eq!(run.shown, ["1279", "960"]);
+}
+
+#[test]
+fn plain_assignment_through_a_path() {
+    let run = run("struct Node { value, next }; let list = Node { value: 1, next: Node { value: 2, next: false } }; list.next.value = 20; show list.next.value");
+    assert_eq!(run.shown, ["20"]);
+}
+
+#[test]
+fn the_object_is_only_evaluated_once() {
+    let run = run("struct Counter { count }; let counter = Counter { count: 0 }; let calls = 0; fn get() { calls += 1; counter };
+        get().count += 5; show counter.count; show calls");
+    assert_eq!(run.shown, ["5", "1"]);
+}
+
+#[test]
+fn only_names_and_properties_can_be_assigned_to() {
+    let run = run("let a = 1; a + 1 = 2");
+    assert!(run.stderr.contains("E0020"), "{}", run.stderr);
+    let run = common::run("fn f() { 1 }; f() += 2");
+    assert!(run.stderr.contains("invalid assignment target"), "{}", run.stderr);
+}
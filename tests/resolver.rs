@@ -0,0 +1,61 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn shadowing_declares_a_new_variable() {
+    let run = run("let x = 1; let f = || x; let x = x + 10; show x; show f()");
+    assert_eq!(run.shown, ["11", "1"]);
+}
+
+#[test]
+fn blocks_and_functions_have_scopes_of_their_own() {
+    let run = run("let x = 1; { let x = 2; x += 1; show x }; fn f(x) { x * 100 }; show f(5); show x");
+    assert_eq!(run.shown, ["3", "500", "1"]);
+}
+
+#[test]
+fn functions_can_call_themselves_and_functions_declared_before_them() {
+    let run = run("fn double(x) { x * 2 }; fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }; show double(fact(5))");
+    assert_eq!(run.shown, ["240"]);
+}
+
+#[test]
+fn closures_see_assignments_to_the_variables_they_captured() {
+    let run = run("let total = 0; fn add(n) { total += n }; add(3); add(4); show total");
+    assert_eq!(run.shown, ["7"]);
+}
+
+#[test]
+fn match_bindings_and_loops() {
+    let run = run("let n = 0; let i = 0; while i < 3 { let n = i * 2; i += 1; show match n { 0 => 0 - 1, n => n } }; show n");
+    assert_eq!(run.shown, ["-1", "2", "4", "0"]);
+}
+
+#[test]
+fn assigning_to_an_undeclared_variable_is_an_error() {
+    let run = run("let count = 0; fn bump() { cuont += 1 }; bump(); show count");
+    assert!(run.stderr.contains("E0021"), "{}", run.stderr);
+    assert!(run.stderr.contains("assignment to undeclared variable cuont"), "{}", run.stderr);
+    assert!(run.shown.is_empty());
+}
+
+#[test]
+fn every_undeclared_assignment_is_reported() {
+    let run = run("a = 1; b += 2");
+    assert_eq!(run.stderr.matches("E0021").count(), 2, "{}", run.stderr);
+}
+
+#[test]
+fn assignments_only_reach_the_scopes_around_them() {
+    for src in ["fn a() { let tmp = 0; tmp }; fn b() { tmp = 5 }; b()", "let r = { let inner = 1; inner }; inner = 2"] {
+        let run = run(src);
+        assert!(run.stderr.contains("E0021"), "{}: {}", src, run.stderr);
+    }
+}
+
+#[test]
+fn assignments_can_reach_names_declared_later_around_them() {
+    let run = run("fn set() { { x = 1 } }; let x = 0; set(); show x; fn outer() { fn inner() { y = 2 }; let y = 0; inner(); y }; show outer()");
+    assert_eq!(run.shown, ["1", "2"]);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn return_leaves_the_function_early() {
+    let run = run("fn sign(x) { if x < 0 { return 0 - 1 }; if x == 0 { return 0 }; 1 }; show sign(0 - 5); show sign(0); show sign(3)");
+    assert_eq!(run.shown, ["-1", "0", "1"]);
+}
+
+#[test]
+fn return_from_inside_loops_and_blocks() {
+    let run = run("fn first_square_above(n) { let i = 0; loop { i += 1; { let square = i * i; if square > n { return square } } } }; show first_square_above(10)");
+    assert_eq!(run.shown, ["16"]);
+}
+
+#[test]
+fn return_in_the_middle_of_an_expression() {
+    let run = run("fn f(x) { 1 + (if x { return 10 } else { 2 }) }; show f(true); show f(false)");
+    assert_eq!(run.shown, ["10", "3"]);
+}
+
+#[test]
+fn return_without_a_value() {
+    let run = run("fn f() { show 1; return; show 2; let unreachable = true }; f(); show 3");
+    assert_eq!(run.shown, ["1", "3"]);
+}
+
+#[test]
+fn return_outside_of_a_function_is_an_error() {
+    let run = run("show 1; return 2");
+    assert!(run.stderr.contains("E0016"), "{}", run.stderr);
+    assert!(run.shown.is_empty());
+}
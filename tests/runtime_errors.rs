@@ -0,0 +1,59 @@
+mod common;
+
+use common::run;
+
+/// the type checker can't see through fields, which lets the errors below get to run
+const ANY: &str = "struct Any { value };\n";
+
+fn error(src: &str) -> String {
+    let run = run(&format!("{}{}", ANY, src));
+    assert!(run.stderr.contains("E0022"), "{}", run.stderr);
+    assert!(!run.stderr.contains("panicked at"), "{}", run.stderr);
+    run.stderr
+}
+
+#[test]
+fn errors_stop_the_program_without_panicking() {
+    let run = run("show 1; show nowhere; show 2");
+    assert_eq!(run.shown, ["1"]);
+    assert!(run.stderr.contains("nowhere is not defined in this scope or any of its parents"), "{}", run.stderr);
+    assert!(!run.stderr.contains("EXECUTED"), "{}", run.stderr);
+}
+
+#[test]
+fn the_trace_lists_the_functions_that_were_running_innermost_first() {
+    let stderr = error("fn inner(x) { x.value() }; fn outer() { inner(Any { value: 5 }) }; outer()");
+    assert!(stderr.contains("5 can't be called"), "{}", stderr);
+    let inner = stderr.find("in inner").expect(&stderr);
+    let outer = stderr.find("in outer").expect(&stderr);
+    let top_level = stderr.find("in <top level>").expect(&stderr);
+    assert!(inner < outer && outer < top_level, "{}", stderr);
+}
+
+#[test]
+fn calls_with_the_wrong_number_of_arguments() {
+    let stderr = error("fn add(a, b) { a + b }; let any = Any { value: add }; any.value(1)");
+    assert!(stderr.contains("add requires 2 arguments but received 1"), "{}", stderr);
+}
+
+#[test]
+fn matches_without_a_matching_arm() {
+    let stderr = error("match 'c' { 'a' => 1, 'b' => 2 }");
+    assert!(stderr.contains("no arm of this match matched 'c'"), "{}", stderr);
+}
+
+#[test]
+fn properties_that_dont_exist() {
+    let stderr = error("enum E { A }; E.B");
+    assert!(stderr.contains("<enum E> has no property B"), "{}", stderr);
+}
+
+#[test]
+fn errors_point_at_the_source_of_every_frame() {
+    let stderr = error("fn inner(x) {\n    x.value()\n};\nfn outer() { inner(Any { value: 5 }) };\nouter()");
+    let lines = stderr.lines().collect::<Vec<_>>();
+    let label_under = |code: &str| lines.iter().rposition(|line| line.ends_with(code)).map(|index| lines[index + 1]).expect(&stderr);
+    assert!(label_under("    x.value()").contains("in inner"), "{}", stderr);
+    assert!(label_under("fn outer() { inner(Any { value: 5 }) };").contains("called from here, in outer"), "{}", stderr);
+    assert!(label_under("outer()").contains("called from here, in <top level>"), "{}", stderr);
+}
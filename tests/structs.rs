@@ -0,0 +1,67 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn literals_fill_fields_in_declaration_order() {
+    let run = run("struct Point { x, y }; let p = Point { y: 2, x: 1 }; show p; show p.x; show p.y");
+    assert_eq!(run.shown, ["Point { x: 1, y: 2 }", "1", "2"]);
+}
+
+#[test]
+fn fields_can_be_annotated() {
+    let run = run("struct Size { width: f64, height: f64 }; let s = Size { width: 3, height: 4 }; show s.width * s.height");
+    assert_eq!(run.shown, ["12"]);
+}
+
+#[test]
+fn struct_values_are_shared_between_copies() {
+    let run = run("struct Counter { count }; let a = Counter { count: 0 }; let b = a; b.count = 5; show a.count");
+    assert_eq!(run.shown, ["5"]);
+}
+
+#[test]
+fn struct_values_nest() {
+    let run = run("struct Point { x, y }; struct Line { from, to }; let l = Line { from: Point { x: 1, y: 2 }, to: Point { x: 3, y: 4 } }; show l.to.x - l.from.y");
+    assert_eq!(run.shown, ["1"]);
+}
+
+#[test]
+fn fields_cant_be_declared_twice() {
+    let run = run("struct Point { x, x }");
+    assert!(run.stderr.contains("E0019"), "{}", run.stderr);
+}
+
+#[test]
+fn literals_have_to_give_every_field() {
+    let run = run("struct Point { x, y }; fn make(S) { S { x: 1 } }; show make(Point)");
+    assert!(run.stderr.contains("missing field y in Point"), "{}", run.stderr);
+    assert!(run.shown.is_empty());
+}
+
+#[test]
+fn literals_of_a_known_struct_are_checked_before_running() {
+    for (src, message) in [
+        ("struct Point { x, y }; show 1; show Point { x: 1 }", "missing field y in Point"),
+        ("struct Point { x, y }; show 1; show Point { x: 1, y: 2, z: 3 }", "Point has no field z"),
+        ("struct Point { x, y }; show 1; show Point { x: 1, x: 2, y: 3 }", "field x of Point is given more than once"),
+    ] {
+        let run = run(src);
+        assert!(run.stderr.contains(message) && run.stderr.contains("E0026"), "{}", run.stderr);
+        assert!(run.shown.is_empty());
+    }
+}
+
+#[test]
+fn annotated_fields_are_type_checked() {
+    let run = run("struct Size { width: f64, height }; show Size { width: 'wide', height: 'any' }");
+    assert!(run.stderr.contains("expected f64, found str"), "{}", run.stderr);
+}
+
+#[test]
+fn unknown_and_repeated_fields_are_told_apart_at_runtime() {
+    let unknown = run("struct Point { x, y }; fn make(S) { S { x: 1, y: 2, z: 3 } }; show make(Point)");
+    assert!(unknown.stderr.contains("Point has no field z"), "{}", unknown.stderr);
+    let repeated = run("struct Point { x, y }; fn make(S) { S { x: 1, x: 2, y: 3 } }; show make(Point)");
+    assert!(repeated.stderr.contains("field x of Point is given more than once"), "{}", repeated.stderr);
+}
@@ -0,0 +1,54 @@
+use eeeee::{engine::EngineError, vm::{self, Overflow}, Engine};
+
+fn eval(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Ok(value) => vm::display(&value),
+        Err(error) => panic!("{}: {:?}", src, error),
+    }
+}
+
+fn diagnostics(engine: &mut Engine, src: &str) -> String {
+    match engine.eval(src, "test") {
+        Err(EngineError::Diagnostics(errors)) => format!("{:?}", errors),
+        other => panic!("{}: expected diagnostics, got {:?}", src, other),
+    }
+}
+
+#[test]
+fn hex_literals_use_all_128_bits() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "0xffffffffffffffffffffffffffffffffu128"), "340282366920938463463374607431768211455");
+    assert_eq!(eval(&mut engine, "0x7fffffffffffffffffffffffffffffffi128"), "170141183460469231731687303715884105727");
+    assert_eq!(eval(&mut engine, "let x: u128 = 0xdeadbeefcafebabe0123456789abcdef; x"), "295990755076957304698161171062762229231");
+}
+
+#[test]
+fn binary_literals_use_all_128_bits() {
+    let mut engine = Engine::new();
+    let ones = "1".repeat(128);
+    assert_eq!(eval(&mut engine, &format!("0b{}u128", ones)), "340282366920938463463374607431768211455");
+    assert_eq!(eval(&mut engine, &format!("0b{}u128", "10".repeat(64))), "226854911280625642308916404954512140970");
+    assert!(diagnostics(&mut engine, &format!("0b{}i128", ones)).contains("this doesn't fit in i128"));
+}
+
+#[test]
+fn the_extremes_of_128_bit_literals() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "-170141183460469231731687303715884105728i128"), "-170141183460469231731687303715884105728");
+    let error = diagnostics(&mut engine, "0x1ffffffffffffffffffffffffffffffffu128");
+    assert!(error.contains("this doesn't fit in u128, which goes from 0 to 340282366920938463463374607431768211455"), "{}", error);
+}
+
+#[test]
+fn arithmetic_on_128_bit_integers() {
+    let mut engine = Engine::new();
+    assert_eq!(eval(&mut engine, "let x: u128 = 18446744073709551616; x * 3"), "55340232221128654848");
+    assert_eq!(eval(&mut engine, "let x: u128 = 0xdeadbeefcafebabe0123456789abcdef; x / 0x100000000"), "68915718020162848918270592359");
+    assert_eq!(eval(&mut engine, "let a: i128 = -7; a / 2"), "-3");
+    assert_eq!(eval(&mut engine, "let x: u64 = 5; x as u128 * 0x10000000000000000"), "92233720368547758080");
+    assert!(diagnostics(&mut engine, "let x: i128 = 0x7fffffffffffffffffffffffffffffffi128; x + 1").contains("addition overflowed i128"));
+    assert!(diagnostics(&mut engine, "let x: u128 = 0; x - 1").contains("subtraction overflowed u128"));
+    engine.set_overflow(Overflow::Wrapping);
+    assert_eq!(eval(&mut engine, "let x: u128 = 0; x - 1"), "340282366920938463463374607431768211455");
+    assert_eq!(eval(&mut engine, "let x: i128 = 0x7fffffffffffffffffffffffffffffffi128; x + 1"), "-170141183460469231731687303715884105728");
+}